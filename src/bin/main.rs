@@ -1,49 +1,251 @@
-//! Vibi with the config from config.rs in interactive mode.
+//! Vibi in interactive or headless mode.
+//!
+//! Run `vibi --help` for options. The built-in config from `config.rs` is
+//! used when no config file is given.
 
 extern crate time;
+extern crate clap;
 extern crate vibi;
 
-use vibi::{window, config};
-use vibi::bismit::{Cortex, Flywheel, /*Subcortex,*/ InputGenerator};
-// use vibi::bismit::subcortex::{};
+use std::process;
+use clap::{App, Arg, ArgMatches};
+use time::Duration;
+use vibi::{window, config, headless, presets};
+use vibi::cycle::parse_iters;
+use vibi::bismit::Flywheel;
 
 
 fn main() {
     use std::thread;
     use std::sync::mpsc;
 
+    let matches = App::new("vibi")
+        .about("OpenGL based testing and experimentation frontend for bismit.")
+        .arg(Arg::with_name("config")
+            .help("Layer map and area scheme config file (TOML)")
+            .index(1))
+        .arg(Arg::with_name("preset")
+            .long("preset")
+            .short("p")
+            .value_name("NAME")
+            .conflicts_with("config")
+            .help("Use a built-in preset instead of a config file (see '--list-presets')"))
+        .arg(Arg::with_name("list-presets")
+            .long("list-presets")
+            .help("List the available presets and exit"))
+        .arg(Arg::with_name("area")
+            .long("area")
+            .short("a")
+            .value_name("NAME")
+            .help("Area to view (overrides the config)"))
+        .arg(Arg::with_name("input")
+            .long("input")
+            .short("i")
+            .value_name("NAME")
+            .multiple(true)
+            .number_of_values(1)
+            .help("Input area driven by an input generator (overrides the config, repeatable)"))
+        .arg(Arg::with_name("show")
+            .long("show")
+            .short("s")
+            .value_name("NAME")
+            .multiple(true)
+            .number_of_values(1)
+            .help("Additional area to display alongside the viewed area (repeatable)"))
+        .arg(Arg::with_name("size")
+            .long("size")
+            .value_name("WIDTHxHEIGHT")
+            .default_value("1600x900")
+            .help("Window size"))
+        .arg(Arg::with_name("fullscreen")
+            .long("fullscreen")
+            .help("Start fullscreen (toggle with 'F11')"))
+        .arg(Arg::with_name("no-vsync")
+            .long("no-vsync")
+            .help("Disable vsync"))
+        .arg(Arg::with_name("multisampling")
+            .long("multisampling")
+            .value_name("LEVEL")
+            .default_value("8")
+            .help("Multisampling level (0 to disable)"))
+        .arg(Arg::with_name("iters")
+            .long("iters")
+            .value_name("N")
+            .default_value("1m")
+            .help("Initial iteration count (accepts 'k' and 'm' suffixes)"))
+        .arg(Arg::with_name("speed")
+            .long("speed")
+            .value_name("LIMIT")
+            .default_value("max")
+            .help("Run speed limit: 'max', 'frame' (one cycle per drawn frame), or cycles \
+                per second (accepts a 'k' suffix)"))
+        .arg(Arg::with_name("autorun")
+            .long("autorun")
+            .short("r")
+            .value_name("N")
+            .help("Run N iterations immediately after startup"))
+        .arg(Arg::with_name("exit")
+            .long("exit")
+            .short("x")
+            .requires("autorun")
+            .help("Exit after the autorun iterations complete"))
+        .arg(Arg::with_name("output-dir")
+            .long("output-dir")
+            .short("o")
+            .value_name("DIR")
+            .default_value(".")
+            .help("Directory where screenshots and recordings are saved"))
+        .arg(Arg::with_name("record")
+            .long("record")
+            .help("Start recording frames as soon as the window opens (toggle with 'R')"))
+        .arg(Arg::with_name("record-every")
+            .long("record-every")
+            .value_name("N")
+            .default_value("100")
+            .help("Number of cycles between recorded frames"))
+        .arg(Arg::with_name("trail-len")
+            .long("trail-len")
+            .value_name("N")
+            .default_value("16")
+            .help("Number of frames over which activity trails fade out"))
+        .arg(Arg::with_name("stats-threshold")
+            .long("stats-threshold")
+            .value_name("STATE")
+            .default_value("128")
+            .help("State at or above which axons are counted in layer statistics"))
+        .arg(Arg::with_name("histogram-bins")
+            .long("histogram-bins")
+            .value_name("N")
+            .default_value("16")
+            .help("Number of bins in state histograms (1-256, toggle histograms with 'H')"))
+        .arg(Arg::with_name("hud")
+            .long("hud")
+            .value_name("SECTIONS")
+            .help("Comma-separated HUD sections to show initially (camera, timing, area, \
+                layers, stats, cell; toggle with 'F2'-'F7' and hide all with 'F1')"))
+        .arg(Arg::with_name("hud-scale")
+            .long("hud-scale")
+            .value_name("SCALE")
+            .default_value("1.0")
+            .help("HUD text scale (applied on top of DPI and window size scaling)"))
+        .arg(Arg::with_name("encoder")
+            .long("encoder")
+            .value_name("COMMAND")
+            .help("Pipe recorded frames (PNG) to the stdin of COMMAND instead of saving \
+                them, e.g. 'ffmpeg -f image2pipe -i - out.mp4'"))
+        .arg(Arg::with_name("record-tract")
+            .long("record-tract")
+            .help("Start recording the raw tract stream as soon as the window opens \
+                (toggle with 'T')"))
+        .arg(Arg::with_name("replay")
+            .long("replay")
+            .value_name("FILE")
+            .conflicts_with_all(&["config", "preset", "headless", "autorun"])
+            .help("Play back a recorded tract stream instead of running a cortex"))
+        .arg(Arg::with_name("headless")
+            .long("headless")
+            .help("Run without opening a window (runs '--autorun' or '--iters' iterations \
+                then exits)"))
+        .arg(Arg::with_name("status-every")
+            .long("status-every")
+            .value_name("MS")
+            .default_value("1000")
+            .help("Status print interval in headless mode (milliseconds)"))
+        .get_matches();
+
+    if matches.is_present("list-presets") {
+        for preset in presets::PRESETS.iter() {
+            println!("{:<12} {}", preset.name, preset.about);
+        }
+        return;
+    }
+
     println!("================= Bismit: vibi::main() running... ==================");
     let time_start = time::get_time();
     // tomfoolery(&time_start);
 
+    let mut win_settings = window_settings(&matches);
+
+    if let Some(path) = matches.value_of("replay") {
+        if !replay(path, win_settings) { process::exit(1); }
+        return;
+    }
+
+    let config = load_config(&matches);
+    win_settings.key_bindings = config.key_bindings.clone();
+
     let (command_tx, command_rx) = mpsc::channel();
     let (request_tx, request_rx) = mpsc::channel();
     let (response_tx, response_rx) = mpsc::channel();
+    let (areas_tx, areas_rx) = mpsc::channel();
 
     let th_flywheel = thread::Builder::new().name("flywheel".to_string()).spawn(move || {
-        let layer_map_schemes = config::define_lm_schemes();
-        let area_schemes = config::define_a_schemes();
-
-        let input_gen = InputGenerator::new(&layer_map_schemes, &area_schemes, "v0").unwrap();
-        // let subcortex = Subcortex::new().nucleus(input_gen);
+        let view_area = config.view_area.clone();
+        let cortex = match config.build_cortex() {
+            Ok(c) => c,
+            Err(e) => {
+                areas_tx.send(Err(e)).ok();
+                return;
+            },
+        };
 
-        let cortex = Cortex::builder(layer_map_schemes, area_schemes)
-            .ca_settings(config::ca_settings())
-            .subcortical_nucleus(input_gen)
-            .build().unwrap();
+        // Area layouts for the window (also signals that the cortex was built):
+        areas_tx.send(Ok(window::describe_areas(&cortex))).ok();
 
-        let mut flywheel = Flywheel::new(cortex, command_rx, "v1");
+        let mut flywheel = Flywheel::new(cortex, command_rx, view_area.as_str());
 
         flywheel.add_req_res_pair(request_rx, response_tx);
         flywheel.spin();
     }).expect("Error creating 'flywheel' thread");
 
-    let th_win = thread::Builder::new().name("win".to_string()).spawn(move || {
-        window::Window::open(command_tx, request_tx, response_rx);
-    }).expect("Error creating 'win' thread");
+    let areas = match areas_rx.recv() {
+        Ok(Ok(areas)) => areas,
+        Ok(Err(e)) => exit_with(&format!("Error building cortex: {}", e)),
+        Err(_) => {
+            let msg = match th_flywheel.join() {
+                Err(e) => headless::panic_message(&e),
+                Ok(()) => "flywheel exited".to_owned(),
+            };
+            exit_with(&format!("Error building cortex: {}", msg));
+        },
+    };
+
+    let mut failed = false;
+
+    if matches.is_present("headless") {
+        let iters = win_settings.autorun_iters.unwrap_or(win_settings.iters);
+        let status_every = match matches.value_of("status-every").unwrap().parse() {
+            Ok(ms) => Duration::milliseconds(ms),
+            Err(_) => exit_with("Invalid status interval."),
+        };
+
+        if let Err(e) = headless::run(command_tx, request_tx, response_rx, th_flywheel, iters,
+                status_every)
+        {
+            println!("VIBI: Headless run failed: {}", e);
+            failed = true;
+        }
+    } else {
+        let th_win = thread::Builder::new().name("win".to_string()).spawn(move || {
+            let source = window::Source::Flywheel {
+                command_tx: command_tx,
+                request_tx: request_tx,
+                response_rx: response_rx,
+                areas: areas,
+            };
+            window::Window::open(source, win_settings);
+        }).expect("Error creating 'win' thread");
 
-    if let Err(e) = th_win.join() { println!("th_win.join(): Error: '{:?}'", e); }
-    if let Err(e) = th_flywheel.join() { println!("th_vin.join(): Error: '{:?}'", e); }
+        if let Err(e) = th_win.join() {
+            println!("th_win.join(): Error: '{}'", headless::panic_message(&e));
+            failed = true;
+        }
+
+        if let Err(e) = th_flywheel.join() {
+            println!("th_flywheel.join(): Error: '{}'", headless::panic_message(&e));
+            failed = true;
+        }
+    }
 
 
     // <<<<< MOVE THIS ELSEWHERE >>>>>
@@ -51,6 +253,171 @@ fn main() {
     let t_sec = time_complete.num_seconds();
     let t_ms = time_complete.num_milliseconds() - (t_sec * 1000);
     println!("\n========= Bismit: vibi::main() complete in: {}.{} seconds =========", t_sec, t_ms);
+
+    if failed { process::exit(1); }
+}
+
+
+/// Opens a window replaying a tract stream file. Returns false on failure.
+fn replay(path: &str, win_settings: window::WindowSettings) -> bool {
+    use std::thread;
+
+    let replay = match window::Replay::open(path) {
+        Ok(r) => r,
+        Err(e) => exit_with(&format!("Error opening replay file '{}': {}", path, e)),
+    };
+    println!("VIBI: Replaying {} frames from '{}'...", replay.frame_count(), path);
+
+    let th_win = thread::Builder::new().name("win".to_string()).spawn(move || {
+        window::Window::open(window::Source::Replay(replay), win_settings);
+    }).expect("Error creating 'win' thread");
+
+    match th_win.join() {
+        Ok(()) => true,
+        Err(e) => {
+            println!("th_win.join(): Error: '{:?}'", e);
+            false
+        },
+    }
+}
+
+
+/// Loads the preset, config file or built-in config and applies command line
+/// overrides.
+fn load_config(matches: &ArgMatches) -> config::Config {
+    let config_path = matches.value_of("config");
+
+    let mut config = if let Some(name) = matches.value_of("preset") {
+        match presets::preset(name) {
+            Some(preset) => {
+                println!("VIBI: Using preset: '{}'.", name);
+                (preset.config)()
+            },
+            None => exit_with(&format!("Unknown preset: '{}' (see '--list-presets')", name)),
+        }
+    } else {
+        let config = match config::Config::load_or_builtin(config_path) {
+            Ok(c) => c,
+            Err(e) => exit_with(&format!("Error loading config: {}", e)),
+        };
+
+        match config_path {
+            Some(p) => println!("VIBI: Using config file: '{}'.", p),
+            None => println!("VIBI: No config file specified, using built-in config."),
+        }
+
+        config
+    };
+
+    if let Some(area) = matches.value_of("area") {
+        config.view_area = area.to_owned();
+    }
+
+    if let Some(inputs) = matches.values_of("input") {
+        config.input_areas = inputs.map(|i| i.to_owned()).collect();
+    }
+
+    // Key bindings are resolved by the window but checked now, along with the
+    // rest of the config:
+    let mut key_bindings = window::KeyBindings::default();
+    for &(ref action, ref keys) in config.key_bindings.iter() {
+        if let Err(e) = key_bindings.rebind(action, keys) {
+            exit_with(&format!("Error loading config: key binding for '{}': {}", action, e));
+        }
+    }
+
+    config
 }
 
 
+/// Builds window settings from command line arguments.
+fn window_settings(matches: &ArgMatches) -> window::WindowSettings {
+    let dims = {
+        let size = matches.value_of("size").unwrap();
+        let mut parts = size.split('x').map(|p| p.trim().parse::<u32>());
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(w)), Some(Ok(h)), None) => (w, h),
+            _ => exit_with(&format!("Invalid window size: '{}' (expected WIDTHxHEIGHT)", size)),
+        }
+    };
+
+    let multisampling = match matches.value_of("multisampling").unwrap().parse() {
+        Ok(ms) => ms,
+        Err(_) => exit_with("Invalid multisampling level."),
+    };
+
+    let iters = parse_iters_arg(matches.value_of("iters").unwrap());
+    let autorun_iters = matches.value_of("autorun").map(parse_iters_arg);
+    let speed_limit = {
+        let speed = matches.value_of("speed").unwrap();
+        match window::SpeedLimit::parse(speed) {
+            Some(limit) => limit,
+            None => exit_with(&format!("Invalid speed limit: '{}'", speed)),
+        }
+    };
+    let record_every = parse_iters_arg(matches.value_of("record-every").unwrap());
+    let trail_len = match matches.value_of("trail-len").unwrap().parse() {
+        Ok(n) if n > 0 => n,
+        _ => exit_with("Invalid trail length."),
+    };
+    let stats_threshold = match matches.value_of("stats-threshold").unwrap().parse() {
+        Ok(t) => t,
+        Err(_) => exit_with("Invalid statistics threshold (expected 0-255)."),
+    };
+    let histogram_bins = match matches.value_of("histogram-bins").unwrap().parse() {
+        Ok(n) if n >= 1 && n <= 256 => n,
+        _ => exit_with("Invalid histogram bin count (expected 1-256)."),
+    };
+    let hud_sections = matches.value_of("hud").map(|sections| {
+        let known = window::Hud::with_defaults().section_names();
+        sections.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).map(|s| {
+            if !known.contains(&s) {
+                exit_with(&format!("Unknown HUD section: '{}' (expected one of: {})", s,
+                    known.join(", ")));
+            }
+            s.to_owned()
+        }).collect()
+    });
+    let hud_scale = match matches.value_of("hud-scale").unwrap().parse() {
+        Ok(s) if s > 0.0 => s,
+        _ => exit_with("Invalid HUD scale."),
+    };
+
+    window::WindowSettings {
+        dims: dims,
+        fullscreen: matches.is_present("fullscreen"),
+        vsync: !matches.is_present("no-vsync"),
+        multisampling: multisampling,
+        iters: iters,
+        speed_limit: speed_limit,
+        autorun_iters: autorun_iters,
+        exit_after_run: matches.is_present("exit"),
+        output_dir: matches.value_of("output-dir").unwrap().into(),
+        record_every: record_every,
+        record_on_start: matches.is_present("record"),
+        encoder_cmd: matches.value_of("encoder").map(|c| c.to_owned()),
+        record_tract_on_start: matches.is_present("record-tract"),
+        extra_areas: matches.values_of("show")
+            .map(|names| names.map(|n| n.to_owned()).collect())
+            .unwrap_or(Vec::new()),
+        trail_len: trail_len,
+        stats_threshold: stats_threshold,
+        histogram_bins: histogram_bins,
+        hud_sections: hud_sections,
+        hud_scale: hud_scale,
+    }
+}
+
+
+fn parse_iters_arg(arg: &str) -> u32 {
+    match parse_iters(arg) {
+        Ok(i) => i,
+        Err(_) => exit_with(&format!("Invalid iteration count: '{}'", arg)),
+    }
+}
+
+
+fn exit_with(msg: &str) -> ! {
+    println!("{}", msg);
+    process::exit(1);
+}
@@ -0,0 +1,750 @@
+//! Declarative (TOML) scheme definitions.
+//!
+//! A config file describes layer maps, areas and cortical area settings. See
+//! `configs/default.toml` for a file equivalent to the built-in defaults.
+//!
+//! Axon tags are referred to by name. Every distinct name is given its own
+//! unique tag (so an output tagged `"foo"` in one layer map connects to an
+//! input tagged `"foo"` in another). Names starting with `@` refer to
+//! predefined tag groups:
+//!
+//! * `@glyph_val`: `GlyphSequences::val_lyr_tags()`
+//! * `@glyph_img`: `GlyphSequences::img_lyr_tags()`
+//!
+//! Unknown keys are rejected rather than ignored, so that a misspelling
+//! doesn't silently fall back to a default.
+//!
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::error::Error as StdError;
+use std::collections::HashMap;
+use toml;
+use bismit::CorticalAreaSettings;
+use bismit::map::{LayerMapSchemeList, LayerMapScheme, LayerMapKind, LayerScheme, LayerTags,
+    CellScheme, TuftScheme, TuftSourceLayer, ControlCellKind, AxonTopology, AxonDomain, AxonTag,
+    InputTrack, AreaSchemeList, AreaScheme, EncoderScheme};
+use bismit::encode::GlyphSequences;
+use super::Config;
+
+
+/// An error loading or interpreting a config file.
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Invalid(String),
+    /// The schemes were valid TOML but a cortex could not be built from them.
+    Build(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::Io(ref err) => write!(f, "config file io error: {}", err),
+            ConfigError::Parse(ref err) => write!(f, "config file parse error: {}", err),
+            ConfigError::Invalid(ref msg) => write!(f, "invalid config: {}", msg),
+            ConfigError::Build(ref msg) => write!(f, "error building cortex: {}", msg),
+        }
+    }
+}
+
+impl StdError for ConfigError {
+    fn description(&self) -> &str {
+        match *self {
+            ConfigError::Io(_) => "config file io error",
+            ConfigError::Parse(_) => "config file parse error",
+            ConfigError::Invalid(_) => "invalid config",
+            ConfigError::Build(_) => "error building cortex",
+        }
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(err: io::Error) -> ConfigError {
+        ConfigError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(err: toml::de::Error) -> ConfigError {
+        ConfigError::Parse(err)
+    }
+}
+
+
+/// The root of a config file.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    /// The area initially displayed. Defaults to the first area without an
+    /// encoder.
+    #[serde(default)]
+    pub view_area: Option<String>,
+    #[serde(default)]
+    pub settings: SettingsDef,
+    #[serde(default, rename = "layer_map")]
+    pub layer_maps: Vec<LayerMapDef>,
+    #[serde(default, rename = "area")]
+    pub areas: Vec<AreaDef>,
+    /// Window key bindings by action name (e.g. `run_stop = "Return"`).
+    #[serde(default)]
+    pub keys: HashMap<String, String>,
+}
+
+impl ConfigFile {
+    /// Reads and parses the file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<ConfigFile, ConfigError> {
+        let mut text = String::new();
+        File::open(path)?.read_to_string(&mut text)?;
+        ConfigFile::parse(&text)
+    }
+
+    /// Parses a config from a string.
+    pub fn parse(text: &str) -> Result<ConfigFile, ConfigError> {
+        toml::from_str(text).map_err(ConfigError::from)
+    }
+
+    /// Converts the file contents into schemes.
+    pub fn into_config(self) -> Result<Config, ConfigError> {
+        if self.areas.is_empty() {
+            return Err(ConfigError::Invalid("no areas defined".to_owned()));
+        }
+
+        let mut tags = TagRegistry::new();
+
+        let mut lm_schemes = LayerMapSchemeList::new();
+        for lm in self.layer_maps.iter() {
+            lm_schemes = lm_schemes.lmap(lm.to_scheme(&mut tags)?);
+        }
+
+        let mut a_schemes = AreaSchemeList::new();
+        for area in self.areas.iter() {
+            a_schemes = a_schemes.area(area.to_scheme(&mut tags)?);
+        }
+
+        let input_areas: Vec<String> = self.areas.iter()
+            .filter(|a| a.encoder.is_some())
+            .map(|a| a.name.clone())
+            .collect();
+
+        let view_area = match self.view_area {
+            Some(name) => {
+                if !self.areas.iter().any(|a| a.name == name) {
+                    return Err(ConfigError::Invalid(format!("unknown view area: '{}'", name)));
+                }
+                name
+            },
+            None => match self.areas.iter().find(|a| a.encoder.is_none()) {
+                Some(a) => a.name.clone(),
+                None => return Err(ConfigError::Invalid("no area to view (all areas have \
+                    encoders)".to_owned())),
+            },
+        };
+
+        let mut key_bindings: Vec<_> = self.keys.into_iter().collect();
+        key_bindings.sort();
+
+        Ok(Config {
+            key_bindings: key_bindings,
+            .. Config::new(lm_schemes, a_schemes, self.settings.to_settings(), input_areas,
+                view_area)
+        })
+    }
+}
+
+
+/// Maps axon tag names to tags.
+struct TagRegistry {
+    tags: HashMap<String, AxonTag>,
+}
+
+impl TagRegistry {
+    fn new() -> TagRegistry {
+        TagRegistry { tags: HashMap::new() }
+    }
+
+    fn resolve(&mut self, names: &[String]) -> Result<Vec<AxonTag>, ConfigError> {
+        let mut resolved = Vec::with_capacity(names.len());
+
+        for name in names {
+            if name.starts_with('@') {
+                match name.as_str() {
+                    "@glyph_val" => resolved.extend_from_slice(GlyphSequences::val_lyr_tags()),
+                    "@glyph_img" => resolved.extend_from_slice(GlyphSequences::img_lyr_tags()),
+                    _ => return Err(ConfigError::Invalid(format!("unknown axon tag group: \
+                        '{}'", name))),
+                }
+            } else {
+                resolved.push(*self.tags.entry(name.clone()).or_insert_with(AxonTag::unique));
+            }
+        }
+
+        Ok(resolved)
+    }
+}
+
+
+/// Cortical area settings (everything enabled by default).
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SettingsDef {
+    #[serde(default)]
+    pub bypass_inhib: bool,
+    #[serde(default)]
+    pub bypass_filters: bool,
+    #[serde(default)]
+    pub disable_pyrs: bool,
+    #[serde(default)]
+    pub disable_ssts: bool,
+    #[serde(default)]
+    pub disable_mcols: bool,
+    #[serde(default)]
+    pub disable_regrowth: bool,
+    #[serde(default)]
+    pub disable_learning: bool,
+}
+
+impl SettingsDef {
+    fn to_settings(&self) -> CorticalAreaSettings {
+        let mut settings = CorticalAreaSettings::new();
+        if self.bypass_inhib { settings = settings.bypass_inhib(); }
+        if self.bypass_filters { settings = settings.bypass_filters(); }
+        if self.disable_pyrs { settings = settings.disable_pyrs(); }
+        if self.disable_ssts { settings = settings.disable_ssts(); }
+        if self.disable_mcols { settings = settings.disable_mcols(); }
+        if self.disable_regrowth { settings = settings.disable_regrowth(); }
+        if self.disable_learning { settings = settings.disable_learning(); }
+        settings
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LayerMapKindDef {
+    Cortical,
+    Subcortical,
+}
+
+
+/// A `LayerMapScheme`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LayerMapDef {
+    pub name: String,
+    pub kind: LayerMapKindDef,
+    #[serde(default, rename = "layer")]
+    pub layers: Vec<LayerDef>,
+}
+
+impl LayerMapDef {
+    fn to_scheme(&self, tags: &mut TagRegistry) -> Result<LayerMapScheme, ConfigError> {
+        let kind = match self.kind {
+            LayerMapKindDef::Cortical => LayerMapKind::Cortical,
+            LayerMapKindDef::Subcortical => LayerMapKind::Subcortical,
+        };
+
+        let mut scheme = LayerMapScheme::new(self.name.as_str(), kind);
+        for layer in self.layers.iter() {
+            scheme = scheme.layer(layer.to_scheme(tags)?);
+        }
+        Ok(scheme)
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AxonTopologyDef {
+    Spatial,
+    Nonspatial,
+}
+
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputTrackDef {
+    Afferent,
+    Efferent,
+}
+
+impl InputTrackDef {
+    fn to_track(&self) -> InputTrack {
+        match *self {
+            InputTrackDef::Afferent => InputTrack::Afferent,
+            InputTrackDef::Efferent => InputTrack::Efferent,
+        }
+    }
+}
+
+
+/// One input source of an input axon domain.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InputSourceDef {
+    pub track: InputTrackDef,
+    pub tags: Vec<String>,
+}
+
+
+/// An `AxonDomain`: either `"local"`, `{ input = [...] }` or `{ output = [...] }`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AxonDomainDef {
+    Local,
+    Input(Vec<InputSourceDef>),
+    Output(Vec<String>),
+}
+
+impl AxonDomainDef {
+    fn to_domain(&self, tags: &mut TagRegistry) -> Result<AxonDomain, ConfigError> {
+        match *self {
+            AxonDomainDef::Local => Ok(AxonDomain::Local),
+            AxonDomainDef::Input(ref srcs) => {
+                let mut src_tags = Vec::with_capacity(srcs.len());
+                for src in srcs.iter() {
+                    src_tags.push((src.track.to_track(), tags.resolve(&src.tags)?));
+                }
+                let sigs: Vec<_> = src_tags.iter()
+                    .map(|&(ref track, ref tags)| (track.clone(), &tags[..]))
+                    .collect();
+                Ok(AxonDomain::input(&sigs[..]))
+            },
+            AxonDomainDef::Output(ref names) => Ok(AxonDomain::output(&tags.resolve(names)?[..])),
+        }
+    }
+}
+
+
+/// A `LayerScheme`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LayerDef {
+    pub name: String,
+    #[serde(default)]
+    pub depth: Option<u8>,
+    /// Layer tag names (`"psal"`, `"ptal"`, `"unused"`).
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub axonal: Option<AxonTopologyDef>,
+    #[serde(default)]
+    pub axon_domain: Option<AxonDomainDef>,
+    #[serde(default)]
+    pub cellular: Option<CellDef>,
+}
+
+impl LayerDef {
+    fn to_scheme(&self, tags: &mut TagRegistry) -> Result<LayerScheme, ConfigError> {
+        let mut scheme = LayerScheme::define(self.name.as_str());
+
+        if let Some(depth) = self.depth {
+            scheme = scheme.depth(depth);
+        }
+        if !self.tags.is_empty() {
+            scheme = scheme.tags(layer_tags(&self.tags)?);
+        }
+        if let Some(topology) = self.axonal {
+            scheme = scheme.axonal(match topology {
+                AxonTopologyDef::Spatial => AxonTopology::Spatial,
+                AxonTopologyDef::Nonspatial => AxonTopology::Nonspatial,
+            });
+        }
+        if let Some(ref domain) = self.axon_domain {
+            scheme = scheme.axon_domain(domain.to_domain(tags)?);
+        }
+        if let Some(ref cellular) = self.cellular {
+            scheme = scheme.cellular(cellular.to_scheme());
+        }
+
+        Ok(scheme)
+    }
+}
+
+fn layer_tags(names: &[String]) -> Result<LayerTags, ConfigError> {
+    let mut tags = LayerTags::DEFAULT;
+    for name in names {
+        tags = tags | match name.as_str() {
+            "default" => LayerTags::DEFAULT,
+            "psal" => LayerTags::PSAL,
+            "ptal" => LayerTags::PTAL,
+            "unused" => LayerTags::UNUSED,
+            _ => return Err(ConfigError::Invalid(format!("unknown layer tag: '{}'", name))),
+        };
+    }
+    Ok(tags)
+}
+
+
+/// A `CellScheme`, selected by `kind`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum CellDef {
+    SpinyStellate {
+        #[serde(default, rename = "tft")]
+        tufts: Vec<TuftDef>,
+    },
+    Pyramidal {
+        #[serde(default, rename = "tft")]
+        tufts: Vec<TuftDef>,
+    },
+    InhibitoryBasketSurround {
+        host_lyr_name: String,
+        field_radius: u8,
+        #[serde(default)]
+        exe_order: usize,
+    },
+    PyrOutputter {
+        host_lyr_name: String,
+        #[serde(default)]
+        exe_order: usize,
+    },
+}
+
+impl CellDef {
+    fn to_scheme(&self) -> CellScheme {
+        match *self {
+            CellDef::SpinyStellate { ref tufts } => {
+                tufts.iter().fold(CellScheme::spiny_stellate(), |cs, t| cs.tft(t.to_scheme()))
+            },
+            CellDef::Pyramidal { ref tufts } => {
+                tufts.iter().fold(CellScheme::pyramidal(), |cs, t| cs.tft(t.to_scheme()))
+            },
+            CellDef::InhibitoryBasketSurround { ref host_lyr_name, field_radius, exe_order } => {
+                CellScheme::control(ControlCellKind::InhibitoryBasketSurround {
+                        host_lyr_name: host_lyr_name.clone(),
+                        field_radius: field_radius,
+                    },
+                    exe_order
+                )
+            },
+            CellDef::PyrOutputter { ref host_lyr_name, exe_order } => {
+                CellScheme::control(ControlCellKind::PyrOutputter {
+                        host_lyr_name: host_lyr_name.clone(),
+                    },
+                    exe_order
+                )
+            },
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TuftKindDef {
+    Basal,
+    Apical,
+}
+
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DenClassDef {
+    Proximal,
+    Distal,
+}
+
+
+/// A `TuftScheme`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TuftDef {
+    pub kind: TuftKindDef,
+    pub den_class: DenClassDef,
+    #[serde(default)]
+    pub dens_per_tft: Option<u32>,
+    #[serde(default)]
+    pub syns_per_den: Option<u32>,
+    #[serde(default)]
+    pub max_active_dens_l2: Option<u8>,
+    #[serde(default)]
+    pub thresh_init: Option<u32>,
+    #[serde(default, rename = "src_lyr")]
+    pub src_lyrs: Vec<TuftSourceDef>,
+}
+
+impl TuftDef {
+    fn to_scheme(&self) -> TuftScheme {
+        let mut tft = match self.kind {
+            TuftKindDef::Basal => TuftScheme::basal(),
+            TuftKindDef::Apical => TuftScheme::apical(),
+        };
+        tft = match self.den_class {
+            DenClassDef::Proximal => tft.proximal(),
+            DenClassDef::Distal => tft.distal(),
+        };
+
+        if let Some(dens_per_tft) = self.dens_per_tft { tft = tft.dens_per_tft(dens_per_tft); }
+        if let Some(syns_per_den) = self.syns_per_den { tft = tft.syns_per_den(syns_per_den); }
+        if let Some(max_l2) = self.max_active_dens_l2 { tft = tft.max_active_dens_l2(max_l2); }
+        if let Some(thresh_init) = self.thresh_init { tft = tft.thresh_init(thresh_init); }
+
+        self.src_lyrs.iter().fold(tft, |tft, src| {
+            tft.src_lyr(TuftSourceLayer::define(src.name.as_str())
+                .syn_reach(src.syn_reach)
+                .prevalence(src.prevalence)
+            )
+        })
+    }
+}
+
+
+/// A `TuftSourceLayer`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TuftSourceDef {
+    pub name: String,
+    pub syn_reach: i8,
+    #[serde(default = "default_prevalence")]
+    pub prevalence: u8,
+}
+
+fn default_prevalence() -> u8 { 1 }
+
+
+/// An `EncoderScheme`, selected by `kind`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum EncoderDef {
+    GlyphSequences { seq_lens: (usize, usize), seq_count: usize, scale: f32, hrz_dims: (u32, u32) },
+    ScalarSequence { range: (f32, f32), incr: f32 },
+    ReversoScalarSequence { range: (f32, f32), incr: f32 },
+    ScalarSdrGradiant { range: (f32, f32), way_span: f32, incr: f32 },
+    SensoryTract,
+    Zeros,
+    Custom,
+}
+
+impl EncoderDef {
+    fn to_scheme(&self) -> EncoderScheme {
+        match *self {
+            EncoderDef::GlyphSequences { seq_lens, seq_count, scale, hrz_dims } => {
+                EncoderScheme::GlyphSequences { seq_lens: seq_lens, seq_count: seq_count,
+                    scale: scale, hrz_dims: hrz_dims }
+            },
+            EncoderDef::ScalarSequence { range, incr } => {
+                EncoderScheme::ScalarSequence { range: range, incr: incr }
+            },
+            EncoderDef::ReversoScalarSequence { range, incr } => {
+                EncoderScheme::ReversoScalarSequence { range: range, incr: incr }
+            },
+            EncoderDef::ScalarSdrGradiant { range, way_span, incr } => {
+                EncoderScheme::ScalarSdrGradiant { range: range, way_span: way_span, incr: incr }
+            },
+            EncoderDef::SensoryTract => EncoderScheme::SensoryTract,
+            EncoderDef::Zeros => EncoderScheme::Zeros,
+            EncoderDef::Custom => EncoderScheme::Custom,
+        }
+    }
+}
+
+
+/// A filter chain applied to an area's input.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FilterChainDef {
+    pub track: InputTrackDef,
+    pub tags: Vec<String>,
+    pub filters: Vec<FilterDef>,
+}
+
+
+/// A filter: either its name (`"retina"`) or `{ name = "...", param = "..." }`.
+///
+/// TOML arrays can't mix the two forms; use tables throughout a chain if any
+/// filter has a parameter.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum FilterDef {
+    Name(String),
+    WithParam {
+        name: String,
+        #[serde(default)]
+        param: Option<String>,
+    },
+}
+
+impl FilterDef {
+    fn to_filter(&self) -> (&str, Option<&str>) {
+        match *self {
+            FilterDef::Name(ref name) => (name.as_str(), None),
+            FilterDef::WithParam { ref name, ref param } => {
+                (name.as_str(), param.as_ref().map(|p| p.as_str()))
+            },
+        }
+    }
+}
+
+
+/// An `AreaScheme`. Exactly one of `side` or `dims` must be given.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AreaDef {
+    pub name: String,
+    pub layer_map: String,
+    #[serde(default)]
+    pub side: Option<u32>,
+    #[serde(default)]
+    pub dims: Option<[u32; 2]>,
+    #[serde(default)]
+    pub encoder: Option<EncoderDef>,
+    #[serde(default)]
+    pub eff_areas: Vec<String>,
+    #[serde(default, rename = "filter_chain")]
+    pub filter_chains: Vec<FilterChainDef>,
+}
+
+impl AreaDef {
+    fn to_scheme(&self, tags: &mut TagRegistry) -> Result<AreaScheme, ConfigError> {
+        let mut scheme = match (self.side, self.dims) {
+            (Some(side), None) => AreaScheme::new(self.name.as_str(), self.layer_map.as_str(), side),
+            (None, Some(dims)) => AreaScheme::irregular(self.name.as_str(),
+                self.layer_map.as_str(), dims),
+            _ => return Err(ConfigError::Invalid(format!("area '{}': exactly one of 'side' or \
+                'dims' must be specified", self.name))),
+        };
+
+        if let Some(ref encoder) = self.encoder {
+            scheme = scheme.encoder(encoder.to_scheme());
+        }
+        if !self.eff_areas.is_empty() {
+            scheme = scheme.eff_areas(self.eff_areas.iter().map(|a| a.as_str()).collect());
+        }
+        for chain in self.filter_chains.iter() {
+            let filters: Vec<_> = chain.filters.iter().map(|f| f.to_filter()).collect();
+            scheme = scheme.filter_chain(chain.track.to_track(), &tags.resolve(&chain.tags)?[..],
+                &filters[..]);
+        }
+
+        Ok(scheme)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{ConfigFile, ConfigError};
+    use config::Config;
+
+    const DEFAULT_TOML: &'static str = include_str!("../../configs/default.toml");
+
+    /// A minimal valid config, with `area` appended as the viewed area's
+    /// table body.
+    fn with_area(area: &str) -> String {
+        format!("[[layer_map]]\nname = \"lm\"\nkind = \"cortical\"\n\n\
+            [[area]]\nname = \"a\"\nlayer_map = \"lm\"\n{}\n", area)
+    }
+
+    fn invalid_msg(text: &str) -> String {
+        match ConfigFile::parse(text).and_then(|f| f.into_config()) {
+            Err(ConfigError::Invalid(msg)) => msg,
+            Err(err) => panic!("unexpected error: {}", err),
+            Ok(_) => panic!("config unexpectedly valid"),
+        }
+    }
+
+    #[test]
+    fn default_file_matches_builtin() {
+        let file = ConfigFile::parse(DEFAULT_TOML).unwrap().into_config().unwrap();
+        let builtin = Config::builtin();
+
+        assert_eq!(file.view_area, builtin.view_area);
+        assert_eq!(file.input_areas, builtin.input_areas);
+
+        let lm_names = |c: &Config| -> Vec<(String, Vec<String>)> {
+            c.lm_schemes.maps().iter().map(|lm| {
+                (lm.name().to_owned(), lm.layers().iter().map(|l| l.name().to_owned()).collect())
+            }).collect()
+        };
+        assert_eq!(lm_names(&file), lm_names(&builtin));
+
+        let areas = |c: &Config| -> Vec<(String, String, u32, u32)> {
+            c.a_schemes.areas().iter().map(|a| {
+                (a.name().to_owned(), a.layer_map_name().to_owned(), a.dims().v_size(),
+                    a.dims().u_size())
+            }).collect()
+        };
+        assert_eq!(areas(&file), areas(&builtin));
+    }
+
+    #[test]
+    fn filter_params() {
+        let text = with_area("side = 16\nfilter_chain = [{ track = \"afferent\", tags = [\"x\"], \
+            filters = [{ name = \"retina\" }, { name = \"smooth\", param = \"3\" }] }]");
+        let file = ConfigFile::parse(&text).unwrap();
+        let filters: Vec<_> = file.areas[0].filter_chains[0].filters.iter()
+            .map(|f| f.to_filter()).collect();
+        assert_eq!(filters, vec![("retina", None), ("smooth", Some("3"))]);
+        assert!(file.into_config().is_ok());
+    }
+
+    #[test]
+    fn unknown_fields() {
+        let texts = [
+            with_area("side = 16\nsides = 16"),
+            format!("veiw_area = \"a\"\n{}", with_area("side = 16")),
+            format!("[settings]\nbypass_inhibit = true\n\n{}", with_area("side = 16")),
+            with_area("side = 16\n\n[[layer_map]]\nname = \"lm2\"\nkind = \"cortical\"\n\
+                [[layer_map.layer]]\nname = \"l\"\naxn_tag = \"x\""),
+            with_area("side = 16\nfilter_chain = [{ track = \"afferent\", tags = [\"x\"], \
+                filters = [{ name = \"retina\", parm = \"3\" }] }]"),
+            with_area("side = 16\nencoder = { kind = \"scalar_sequence\", range = [0.0, 1.0], \
+                incr = 0.1, inc = 0.1 }"),
+        ];
+        for text in texts.iter() {
+            match ConfigFile::parse(text) {
+                Err(ConfigError::Parse(_)) => (),
+                other => panic!("unknown field accepted: {:?}", other.map(|_| ())),
+            }
+        }
+    }
+
+    #[test]
+    fn no_areas() {
+        assert!(invalid_msg("view_area = \"a\"\n").contains("no areas"));
+    }
+
+    #[test]
+    fn side_and_dims() {
+        assert!(invalid_msg(&with_area("side = 16\ndims = [16, 16]")).contains("exactly one"));
+        assert!(invalid_msg(&with_area("")).contains("exactly one"));
+    }
+
+    #[test]
+    fn unknown_tag_group() {
+        let text = with_area("side = 16\nfilter_chain = [{ track = \"afferent\", \
+            tags = [\"@nope\"], filters = [\"retina\"] }]");
+        assert!(invalid_msg(&text).contains("unknown axon tag group"));
+    }
+
+    #[test]
+    fn unknown_layer_tag() {
+        let text = "[[layer_map]]\nname = \"lm\"\nkind = \"cortical\"\n\
+            [[layer_map.layer]]\nname = \"l\"\ntags = [\"nope\"]\n\n\
+            [[area]]\nname = \"a\"\nlayer_map = \"lm\"\nside = 16\n";
+        assert!(invalid_msg(text).contains("unknown layer tag"));
+    }
+
+    #[test]
+    fn unknown_view_area() {
+        let text = format!("view_area = \"b\"\n{}", with_area("side = 16"));
+        assert!(invalid_msg(&text).contains("unknown view area"));
+    }
+
+    #[test]
+    fn key_bindings() {
+        let text = with_area("side = 16\n\n[keys]\nscreenshot = \"F12, Ctrl+S\"\nexit = \"\"");
+        let config = ConfigFile::parse(&text).unwrap().into_config().unwrap();
+        assert_eq!(config.key_bindings, vec![("exit".to_owned(), "".to_owned()),
+            ("screenshot".to_owned(), "F12, Ctrl+S".to_owned())]);
+
+        // Names are left for the window to resolve:
+        let text = with_area("side = 16\n\n[keys]\nscreen_shot = \"F13\"");
+        let config = ConfigFile::parse(&text).unwrap().into_config().unwrap();
+        assert_eq!(config.key_bindings, vec![("screen_shot".to_owned(), "F13".to_owned())]);
+    }
+}
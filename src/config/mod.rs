@@ -1,11 +1,90 @@
-//! Default configuration for vibi used when run as binary.
+//! Configuration for vibi used when run as binary.
+//!
+//! Schemes are normally loaded from a TOML file (see `ConfigFile`). The
+//! built-in defaults defined below are used only when no file is given.
 
-use bismit::CorticalAreaSettings;
+use std::path::Path;
+use bismit::{Cortex, CorticalAreaSettings, InputGenerator};
 use bismit::map::*;
 use bismit::encode::GlyphSequences;
 
+mod file;
+
+pub use self::file::{ConfigFile, ConfigError};
+
+
+/// Everything needed to build a `Cortex` and point vibi at it.
+pub struct Config {
+    pub lm_schemes: LayerMapSchemeList,
+    pub a_schemes: AreaSchemeList,
+    pub ca_settings: CorticalAreaSettings,
+    /// Subcortical areas which are driven by an `InputGenerator`.
+    pub input_areas: Vec<String>,
+    /// The area initially displayed.
+    pub view_area: String,
+    /// (action, keys) pairs replacing the window's default key bindings, as
+    /// written (see `window::KeyBindings::rebind`).
+    pub key_bindings: Vec<(String, String)>,
+}
+
+impl Config {
+    /// Returns a new configuration using the window's default key bindings.
+    pub fn new(lm_schemes: LayerMapSchemeList, a_schemes: AreaSchemeList,
+            ca_settings: CorticalAreaSettings, input_areas: Vec<String>, view_area: String)
+            -> Config
+    {
+        Config {
+            lm_schemes: lm_schemes,
+            a_schemes: a_schemes,
+            ca_settings: ca_settings,
+            input_areas: input_areas,
+            view_area: view_area,
+            key_bindings: Vec::new(),
+        }
+    }
+
+    /// Returns the built-in default configuration.
+    pub fn builtin() -> Config {
+        Config::new(define_lm_schemes(), define_a_schemes(), ca_settings(),
+            vec!["v0".to_owned()], "v1".to_owned())
+    }
+
+    /// Loads a configuration from a TOML file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
+        ConfigFile::load(path)?.into_config()
+    }
+
+    /// Loads the file at `path` if one is given, otherwise returns the
+    /// built-in defaults.
+    pub fn load_or_builtin<P: AsRef<Path>>(path: Option<P>) -> Result<Config, ConfigError> {
+        match path {
+            Some(p) => Config::load(p),
+            None => Ok(Config::builtin()),
+        }
+    }
+
+    /// Builds a cortex with an input generator attached for each input area.
+    pub fn build_cortex(self) -> Result<Cortex, ConfigError> {
+        let mut input_gens = Vec::with_capacity(self.input_areas.len());
+        for area_name in self.input_areas.iter() {
+            let input_gen = InputGenerator::new(&self.lm_schemes, &self.a_schemes,
+                area_name.as_str()).map_err(|err| ConfigError::Build(format!("input area \
+                    '{}': {}", area_name, err)))?;
+            input_gens.push(input_gen);
+        }
+
+        let mut builder = Cortex::builder(self.lm_schemes, self.a_schemes)
+            .ca_settings(self.ca_settings);
+
+        for input_gen in input_gens {
+            builder = builder.subcortical_nucleus(input_gen);
+        }
+
+        builder.build().map_err(|err| ConfigError::Build(err.to_string()))
+    }
+}
+
 
-/* Eventually move defines to a config file or some such */
 pub fn define_lm_schemes() -> LayerMapSchemeList {
     // const MOTOR_UID: u32 = 543;
     // const OLFAC_UID: u32 = 654;
@@ -1,3 +1,3 @@
-pub use self::cycle::{CycleLoop, CyCtl, CyRes, Status, AreaInfo};
+pub use self::cycle::{CycleLoop, CyCtl, CyRes, Status, AreaInfo, parse_iters};
 
 mod cycle;
\ No newline at end of file
@@ -0,0 +1,110 @@
+//! Drives a flywheel without opening a window.
+
+use std::any::Any;
+use std::thread::{self, JoinHandle};
+use std::time::Duration as StdDuration;
+use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+use time::{self, Duration};
+use bismit::flywheel::{Command, Request, Response, Status};
+
+
+/// Runs `iters` cycles, printing the cycle status every `status_every`, then
+/// tells the flywheel to exit and joins its thread.
+///
+/// Returns the final status once the run has completed or an error message
+/// if the flywheel stops responding or its thread panics.
+pub fn run(command_tx: Sender<Command>, request_tx: Sender<Request>,
+        response_rx: Receiver<Response>, flywheel: JoinHandle<()>, iters: u32,
+        status_every: Duration) -> Result<Status, String>
+{
+    let result = run_cycles(&command_tx, &request_tx, &response_rx, iters, status_every);
+    command_tx.send(Command::Exit).ok();
+
+    match flywheel.join() {
+        Ok(()) => result,
+        Err(err) => Err(format!("Flywheel thread panicked: {}", panic_message(&err))),
+    }
+}
+
+
+/// Returns the message a thread panicked with, if it was a string.
+pub fn panic_message(err: &Box<Any + Send>) -> String {
+    if let Some(msg) = err.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = err.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "(unknown)".to_owned()
+    }
+}
+
+
+fn run_cycles(command_tx: &Sender<Command>, request_tx: &Sender<Request>,
+        response_rx: &Receiver<Response>, iters: u32, status_every: Duration)
+        -> Result<Status, String>
+{
+    // Get the initial status (to determine the total cycle count goal):
+    request_status(command_tx, request_tx)?;
+
+    let initial_ttl_cycles = loop {
+        match response_rx.recv() {
+            Ok(Response::Status(status)) => break status.ttl_cycles(),
+            Ok(Response::Exiting) => return Err("Flywheel exited before starting.".to_owned()),
+            Ok(_) => (),
+            Err(_) => return Err("Flywheel disconnected before starting.".to_owned()),
+        }
+    };
+    let target_cycles = initial_ttl_cycles + iters;
+
+    println!("VIBI: Running {} iterations (headless)...", iters);
+    command_tx.send(Command::Iterate(iters))
+        .map_err(|e| format!("Error sending iterate command: {}", e))?;
+
+    let mut started = false;
+    let mut next_status = time::get_time() + status_every;
+
+    loop {
+        if time::get_time() >= next_status {
+            request_status(command_tx, request_tx)?;
+            next_status = time::get_time() + status_every;
+        }
+
+        match response_rx.try_recv() {
+            Ok(Response::Status(status)) => {
+                if status.cycling {
+                    started = true;
+                    print_status(&status);
+                } else if started || status.ttl_cycles() >= target_cycles {
+                    print_status(&status);
+                    return Ok(*status);
+                }
+            },
+            Ok(Response::Exiting) => return Err("Flywheel exited during run.".to_owned()),
+            Ok(_) => (),
+            Err(TryRecvError::Empty) => thread::sleep(StdDuration::from_millis(10)),
+            Err(TryRecvError::Disconnected) => {
+                return Err("Flywheel disconnected during run.".to_owned());
+            },
+        }
+    }
+}
+
+
+/// Requests the flywheel's status.
+///
+/// An idle flywheel waits on its command channel and only handles queued
+/// requests once a command arrives (as the window does, see
+/// `window::Window::open`), so `Command::None` follows the request. Without
+/// it the status ending a run may never arrive.
+fn request_status(command_tx: &Sender<Command>, request_tx: &Sender<Request>)
+        -> Result<(), String>
+{
+    request_tx.send(Request::Status).map_err(|e| format!("Error requesting status: {}", e))?;
+    command_tx.send(Command::None).map_err(|e| format!("Error sending command: {}", e))
+}
+
+
+fn print_status(status: &Status) {
+    printlnc!(yellow: "[cycles: {} @ {:.1} c/s | total: {} @ {:.1} c/s]",
+        status.cur_cycle(), status.cur_cps(), status.ttl_cycles(), status.ttl_cps());
+}
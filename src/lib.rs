@@ -5,7 +5,7 @@
 
 #[macro_use] extern crate glium;
 extern crate glium_text_rusttype;
-// extern crate image;
+extern crate image;
 extern crate time;
 // extern crate find_folder;
 // extern crate num;
@@ -13,6 +13,9 @@ extern crate time;
 // extern crate vecmath;
 // extern crate rustc_serialize;
 extern crate rand;
+extern crate serde;
+#[macro_use] extern crate serde_derive;
+extern crate toml;
 // #[macro_use] extern crate log;
 // extern crate futures;
 #[macro_use] extern crate colorify;
@@ -20,7 +23,9 @@ extern crate enamel;
 pub extern crate bismit;
 
 pub mod config;
+pub mod presets;
 pub mod window;
+pub mod headless;
 #[macro_use] pub mod cycle;
 // mod util;
 // mod ui;
@@ -0,0 +1,129 @@
+//! Glyph sequences: encodes sequences of glyph images and their values.
+
+use bismit::CorticalAreaSettings;
+use bismit::map::*;
+use bismit::encode::GlyphSequences;
+use config::Config;
+
+
+pub fn config() -> Config {
+    Config::new(define_lm_schemes(), define_a_schemes(), ca_settings(),
+        vec!["v0".to_owned()], "v1".to_owned())
+}
+
+
+pub fn define_lm_schemes() -> LayerMapSchemeList {
+    LayerMapSchemeList::new()
+        .lmap(LayerMapScheme::new("visual", LayerMapKind::Cortical)
+            .layer(LayerScheme::define("glyph_val")
+                .axonal(AxonTopology::Nonspatial)
+                .axon_domain(AxonDomain::input(&[(InputTrack::Afferent,
+                    GlyphSequences::val_lyr_tags())]))
+            )
+            .layer(LayerScheme::define("glyph_img")
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::input(&[(InputTrack::Afferent,
+                    GlyphSequences::img_lyr_tags())]))
+            )
+            .layer(LayerScheme::define("iv")
+                .depth(1)
+                .tags(LayerTags::PSAL)
+                .axon_domain(AxonDomain::Local)
+                .cellular(CellScheme::spiny_stellate()
+                    .tft(TuftScheme::basal().proximal()
+                        .syns_per_den(128)
+                        .thresh_init(400)
+                        .src_lyr(TuftSourceLayer::define("glyph_img")
+                            .syn_reach(12)
+                            .prevalence(1)
+                        )
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iv_inhib")
+                .cellular(CellScheme::control(
+                        ControlCellKind::InhibitoryBasketSurround {
+                            host_lyr_name: "iv".into(),
+                            field_radius: 4,
+                        },
+                        0
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iii")
+                .depth(2)
+                .tags(LayerTags::PTAL)
+                .axon_domain(AxonDomain::output(&[AxonTag::unique()]))
+                .cellular(CellScheme::pyramidal()
+                    .tft(TuftScheme::basal().distal()
+                        .dens_per_tft(2)
+                        .syns_per_den(64)
+                        .thresh_init(500)
+                        .src_lyr(TuftSourceLayer::define("iii")
+                            .syn_reach(14)
+                            .prevalence(1)
+                        )
+                    )
+                    .tft(TuftScheme::apical().distal()
+                        .dens_per_tft(2)
+                        .syns_per_den(64)
+                        .thresh_init(500)
+                        .src_lyr(TuftSourceLayer::define("glyph_val")
+                            .syn_reach(18)
+                            .prevalence(1)
+                        )
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iii_output")
+                .cellular(CellScheme::control(
+                        ControlCellKind::PyrOutputter {
+                            host_lyr_name: "iii".into(),
+                        },
+                        0
+                    )
+                )
+            )
+        )
+        .lmap(LayerMapScheme::new("v0_lm", LayerMapKind::Subcortical)
+            .layer(LayerScheme::define("ext_glyph_val")
+                .depth(1)
+                .axonal(AxonTopology::Nonspatial)
+                .axon_domain(AxonDomain::output(GlyphSequences::val_lyr_tags()))
+            )
+            .layer(LayerScheme::define("ext_glyph_img")
+                .depth(1)
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::output(GlyphSequences::img_lyr_tags()))
+            )
+        )
+}
+
+
+pub fn define_a_schemes() -> AreaSchemeList {
+    const ENCODE_SIZE: u32 = 64;
+    const AREA_SIDE: u32 = 48;
+
+    AreaSchemeList::new()
+        .area(AreaScheme::new("v0", "v0_lm", ENCODE_SIZE)
+            .encoder(EncoderScheme::GlyphSequences { seq_lens: (5, 5), seq_count: 10,
+                scale: 1.4, hrz_dims: (16, 16) })
+        )
+        .area(AreaScheme::new("v1", "visual", AREA_SIDE)
+            .eff_areas(vec!["v0"])
+            .filter_chain(InputTrack::Afferent, GlyphSequences::img_lyr_tags(),
+                &[("retina", None)])
+        )
+}
+
+
+pub fn ca_settings() -> CorticalAreaSettings {
+    CorticalAreaSettings::new()
+        // .bypass_inhib()
+        // .bypass_filters()
+        // .disable_pyrs()
+        // .disable_ssts()
+        // .disable_mcols()
+        // .disable_regrowth()
+        // .disable_learning()
+}
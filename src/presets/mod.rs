@@ -0,0 +1,46 @@
+//! Built-in scenarios selectable by name (`vibi --preset NAME`).
+
+use config::Config;
+
+pub mod glyph_seq;
+pub mod scalar_sdr;
+pub mod skewed_hex;
+pub mod scalar_seq;
+pub mod multi;
+pub mod motor;
+pub mod multi_cere;
+
+
+/// A named, built-in configuration.
+pub struct Preset {
+    pub name: &'static str,
+    pub about: &'static str,
+    pub config: fn() -> Config,
+}
+
+
+/// All available presets.
+pub static PRESETS: &'static [Preset] = &[
+    Preset { name: "default", about: "The built-in default config (glyph sequences)",
+        config: Config::builtin },
+    Preset { name: "glyph_seq", about: "Glyph images and values in sequence",
+        config: glyph_seq::config },
+    Preset { name: "scalar_sdr", about: "Scalar value encoded as a sparse gradient",
+        config: scalar_sdr::config },
+    Preset { name: "skewed_hex", about: "Irregular input area feeding a large area",
+        config: skewed_hex::config },
+    Preset { name: "scalar_seq", about: "Sequence of scalar values",
+        config: scalar_seq::config },
+    Preset { name: "multi", about: "Three input areas feeding one area",
+        config: multi::config },
+    Preset { name: "motor", about: "Two layer input area and a layer v",
+        config: motor::config },
+    Preset { name: "multi_cere", about: "Input area feeding two cortical areas in series",
+        config: multi_cere::config },
+];
+
+
+/// Returns the preset named `name`.
+pub fn preset(name: &str) -> Option<&'static Preset> {
+    PRESETS.iter().find(|p| p.name == name)
+}
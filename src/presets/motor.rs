@@ -0,0 +1,153 @@
+//! Motor: a two layer input area feeding an area with a layer v output.
+//!
+//! The original example drove `v0` with a `HexMoldTest` encoder set directly
+//! on the thalamic pathway of an already built cortex (alongside a
+//! `TestScNucleus`). Encoders are now created by `InputGenerator` from the
+//! area's `EncoderScheme`, which has no `HexMoldTest` variant, so this uses
+//! the two layer reverso scalar sequence the original listed as its
+//! alternative. Activity therefore sweeps back and forth across both input
+//! layers rather than showing fixed hexagonal molds.
+//!
+//! The original's `eff_in` layer (efferent input, read by nothing but a
+//! commented out apical tuft) is left out.
+
+use bismit::CorticalAreaSettings;
+use bismit::map::*;
+use config::Config;
+
+const ENCODE_SIZE: u32 = 48;
+const AREA_SIDE: u32 = 48;
+
+
+pub fn config() -> Config {
+    Config::new(define_lm_schemes(), define_a_schemes(), ca_settings(),
+        vec!["v0".to_owned()], "v1".to_owned())
+}
+
+
+pub fn define_lm_schemes() -> LayerMapSchemeList {
+    let at0 = AxonTag::unique();
+    let at1 = AxonTag::unique();
+
+    LayerMapSchemeList::new()
+        .lmap(LayerMapScheme::new("v1_lm", LayerMapKind::Cortical)
+            .layer(LayerScheme::define("aff_in_0")
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::input(&[(InputTrack::Afferent, &[at0])]))
+            )
+            .layer(LayerScheme::define("aff_in_1")
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::input(&[(InputTrack::Afferent, &[at1])]))
+            )
+            .layer(LayerScheme::define("iv")
+                .depth(1)
+                .tags(LayerTags::PSAL)
+                .axon_domain(AxonDomain::Local)
+                .cellular(CellScheme::spiny_stellate()
+                    .tft(TuftScheme::basal().proximal()
+                        .syns_per_den(64)
+                        .thresh_init(600)
+                        .src_lyr(TuftSourceLayer::define("aff_in_0")
+                            .syn_reach(14)
+                            .prevalence(1)
+                        )
+                        .src_lyr(TuftSourceLayer::define("aff_in_1")
+                            .syn_reach(10)
+                            .prevalence(1)
+                        )
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iv_inhib")
+                .cellular(CellScheme::control(
+                        ControlCellKind::InhibitoryBasketSurround {
+                            host_lyr_name: "iv".into(),
+                            field_radius: 4,
+                        },
+                        0
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iii")
+                .depth(2)
+                .tags(LayerTags::PTAL)
+                .axon_domain(AxonDomain::output(&[AxonTag::unique()]))
+                .cellular(CellScheme::pyramidal()
+                    .tft(TuftScheme::basal().distal()
+                        .dens_per_tft(2)
+                        .syns_per_den(32)
+                        .thresh_init(500)
+                        .src_lyr(TuftSourceLayer::define("iii")
+                            .syn_reach(12)
+                            .prevalence(1)
+                        )
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iii_output")
+                .cellular(CellScheme::control(
+                        ControlCellKind::PyrOutputter {
+                            host_lyr_name: "iii".into(),
+                        },
+                        0
+                    )
+                )
+            )
+            .layer(LayerScheme::define("v")
+                .depth(1)
+                .axon_domain(AxonDomain::Local)
+                .cellular(CellScheme::pyramidal()
+                    .tft(TuftScheme::basal().distal()
+                        .dens_per_tft(2)
+                        .syns_per_den(32)
+                        .thresh_init(500)
+                        .src_lyr(TuftSourceLayer::define("iii")
+                            .syn_reach(12)
+                            .prevalence(4)
+                        )
+                        .src_lyr(TuftSourceLayer::define("v")
+                            .syn_reach(20)
+                            .prevalence(1)
+                        )
+                    )
+                )
+            )
+        )
+        .lmap(LayerMapScheme::new("v0_lm", LayerMapKind::Subcortical)
+            .layer(LayerScheme::define("external_0")
+                .depth(1)
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::output(&[at0]))
+            )
+            .layer(LayerScheme::define("external_1")
+                .depth(1)
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::output(&[at1]))
+            )
+        )
+}
+
+
+pub fn define_a_schemes() -> AreaSchemeList {
+    // ENCODE_SIZE: 64 --> range: (0.0, 172.0)
+    // ENCODE_SIZE: 32 --> range: (0.0, 76.0)
+    AreaSchemeList::new()
+        .area(AreaScheme::new("v0", "v0_lm", ENCODE_SIZE)
+            .encoder(EncoderScheme::ReversoScalarSequence { range: (0.0, 124.0), incr: 1.0 })
+        )
+        .area(AreaScheme::new("v1", "v1_lm", AREA_SIDE)
+            .eff_areas(vec!["v0"])
+        )
+}
+
+
+pub fn ca_settings() -> CorticalAreaSettings {
+    CorticalAreaSettings::new()
+        // .bypass_inhib()
+        // .bypass_filters()
+        // .disable_pyrs()
+        // .disable_ssts()
+        // .disable_mcols()
+        // .disable_regrowth()
+        // .disable_learning()
+}
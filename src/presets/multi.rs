@@ -0,0 +1,172 @@
+//! Multiple input areas: three scalar sequence encoders feeding one area.
+
+use bismit::CorticalAreaSettings;
+use bismit::map::*;
+use config::Config;
+
+
+/// Tags connecting the input areas to `v1`.
+#[derive(Clone, Copy)]
+pub struct Tags {
+    /// Shared by `external_0` and `external_2`.
+    pub ff: AxonTag,
+    pub u1: AxonTag,
+    pub u3: AxonTag,
+}
+
+impl Tags {
+    pub fn new() -> Tags {
+        Tags { ff: AxonTag::unique(), u1: AxonTag::unique(), u3: AxonTag::unique() }
+    }
+}
+
+
+pub fn config() -> Config {
+    let tags = Tags::new();
+
+    Config::new(define_lm_schemes(tags), define_a_schemes(tags), ca_settings(),
+        vec!["v0_0".to_owned(), "v0_1".to_owned(), "v0_2".to_owned()], "v1".to_owned())
+}
+
+
+pub fn define_lm_schemes(tags: Tags) -> LayerMapSchemeList {
+    LayerMapSchemeList::new()
+        .lmap(LayerMapScheme::new("visual", LayerMapKind::Cortical)
+            .layer(LayerScheme::define("motor_ctx")
+                .axonal(AxonTopology::Nonspatial)
+                .axon_domain(AxonDomain::input(&[(InputTrack::Afferent, &[AxonTag::unique()])]))
+            )
+            .layer(LayerScheme::define("aff_in")
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::input(&[(InputTrack::Afferent, &[tags.ff])]))
+            )
+            .layer(LayerScheme::define("aff_in_1")
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::input(&[(InputTrack::Afferent, &[tags.u1])]))
+            )
+            .layer(LayerScheme::define("aff_in_3")
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::input(&[(InputTrack::Afferent, &[tags.u3])]))
+            )
+            .layer(LayerScheme::define("unused")
+                .depth(1)
+                .tags(LayerTags::UNUSED)
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::Local)
+            )
+            .layer(LayerScheme::define("iv")
+                .depth(1)
+                .tags(LayerTags::PSAL)
+                .axon_domain(AxonDomain::Local)
+                .cellular(CellScheme::spiny_stellate()
+                    .tft(TuftScheme::basal().proximal()
+                        .syns_per_den(64)
+                        .thresh_init(400)
+                        .src_lyr(TuftSourceLayer::define("aff_in")
+                            .syn_reach(14)
+                            .prevalence(1)
+                        )
+                        .src_lyr(TuftSourceLayer::define("aff_in_1")
+                            .syn_reach(14)
+                            .prevalence(1)
+                        )
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iv_inhib")
+                .cellular(CellScheme::control(
+                        ControlCellKind::InhibitoryBasketSurround {
+                            host_lyr_name: "iv".into(),
+                            field_radius: 4,
+                        },
+                        0
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iii")
+                .depth(2)
+                .tags(LayerTags::PTAL)
+                .axon_domain(AxonDomain::output(&[AxonTag::unique()]))
+                .cellular(CellScheme::pyramidal()
+                    .tft(TuftScheme::basal().distal()
+                        .dens_per_tft(2)
+                        .syns_per_den(32)
+                        .thresh_init(500)
+                        .src_lyr(TuftSourceLayer::define("iii")
+                            .syn_reach(20)
+                            .prevalence(1)
+                        )
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iii_output")
+                .cellular(CellScheme::control(
+                        ControlCellKind::PyrOutputter {
+                            host_lyr_name: "iii".into(),
+                        },
+                        0
+                    )
+                )
+            )
+        )
+        .lmap(LayerMapScheme::new("v0_lm", LayerMapKind::Subcortical)
+            .layer(LayerScheme::define("external_0")
+                .depth(1)
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::output(&[tags.ff]))
+            )
+            .layer(LayerScheme::define("external_1")
+                .depth(1)
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::output(&[tags.u1]))
+            )
+        )
+        .lmap(LayerMapScheme::new("v0_lm_2", LayerMapKind::Subcortical)
+            .layer(LayerScheme::define("external_2")
+                .depth(1)
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::output(&[tags.ff]))
+            )
+            .layer(LayerScheme::define("external_3")
+                .depth(1)
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::output(&[tags.u3]))
+            )
+        )
+}
+
+
+pub fn define_a_schemes(tags: Tags) -> AreaSchemeList {
+    // ENCODE_SIZE: 64 --> range: (0.0, 172.0)
+    // ENCODE_SIZE: 32 --> range: (0.0, 76.0)
+    const ENCODE_SIZE: u32 = 32;
+    const AREA_SIDE: u32 = 48;
+
+    AreaSchemeList::new()
+        .area(AreaScheme::new("v0_0", "v0_lm", ENCODE_SIZE)
+            .encoder(EncoderScheme::ReversoScalarSequence { range: (0.0, 76.0), incr: 1.0 })
+        )
+        .area(AreaScheme::new("v0_1", "v0_lm_2", 24)
+            .encoder(EncoderScheme::ReversoScalarSequence { range: (0.0, 76.0), incr: 1.0 })
+        )
+        .area(AreaScheme::new("v0_2", "v0_lm", ENCODE_SIZE)
+            .encoder(EncoderScheme::ReversoScalarSequence { range: (0.0, 76.0), incr: 1.0 })
+        )
+        .area(AreaScheme::new("v1", "visual", AREA_SIDE)
+            .eff_areas(vec!["v0_0", "v0_1", "v0_2"])
+            .filter_chain(InputTrack::Afferent, &[tags.u1], &[("retina", None)])
+            .filter_chain(InputTrack::Afferent, &[tags.ff], &[("retina", None)])
+        )
+}
+
+
+pub fn ca_settings() -> CorticalAreaSettings {
+    CorticalAreaSettings::new()
+        // .bypass_inhib()
+        // .bypass_filters()
+        // .disable_pyrs()
+        // .disable_ssts()
+        // .disable_mcols()
+        // .disable_regrowth()
+        // .disable_learning()
+}
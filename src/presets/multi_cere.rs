@@ -0,0 +1,204 @@
+//! Multiple cortical areas: a two layer input area feeding `v1`, which in
+//! turn feeds `m1`.
+
+use bismit::CorticalAreaSettings;
+use bismit::map::*;
+use config::Config;
+
+
+pub fn config() -> Config {
+    Config::new(define_lm_schemes(), define_a_schemes(), ca_settings(),
+        vec!["v0".to_owned()], "m1".to_owned())
+}
+
+
+pub fn define_lm_schemes() -> LayerMapSchemeList {
+    let at_u0 = AxonTag::unique();
+    let at_u1 = AxonTag::unique();
+    let at_v1_out = AxonTag::unique();
+
+    LayerMapSchemeList::new()
+        .lmap(LayerMapScheme::new("v1_lm", LayerMapKind::Cortical)
+            .layer(LayerScheme::define("aff_in_0")
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::input(&[(InputTrack::Afferent, &[at_u0])]))
+            )
+            .layer(LayerScheme::define("aff_in_1")
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::input(&[(InputTrack::Afferent, &[at_u1])]))
+            )
+            .layer(LayerScheme::define("iv")
+                .depth(1)
+                .tags(LayerTags::PSAL)
+                .axon_domain(AxonDomain::Local)
+                .cellular(CellScheme::spiny_stellate()
+                    .tft(TuftScheme::basal().proximal()
+                        .syns_per_den(64)
+                        .thresh_init(400)
+                        .src_lyr(TuftSourceLayer::define("aff_in_0")
+                            .syn_reach(14)
+                            .prevalence(1)
+                        )
+                        .src_lyr(TuftSourceLayer::define("aff_in_1")
+                            .syn_reach(14)
+                            .prevalence(1)
+                        )
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iv_inhib")
+                .cellular(CellScheme::control(
+                        ControlCellKind::InhibitoryBasketSurround {
+                            host_lyr_name: "iv".into(),
+                            field_radius: 4,
+                        },
+                        0
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iii")
+                .depth(2)
+                .tags(LayerTags::PTAL)
+                .axon_domain(AxonDomain::output(&[at_v1_out]))
+                .cellular(CellScheme::pyramidal()
+                    .tft(TuftScheme::basal().distal()
+                        .dens_per_tft(2)
+                        .syns_per_den(32)
+                        .thresh_init(500)
+                        .src_lyr(TuftSourceLayer::define("iii")
+                            .syn_reach(20)
+                            .prevalence(1)
+                        )
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iii_output")
+                .cellular(CellScheme::control(
+                        ControlCellKind::PyrOutputter {
+                            host_lyr_name: "iii".into(),
+                        },
+                        0
+                    )
+                )
+            )
+            .layer(LayerScheme::define("v")
+                .depth(1)
+                .axon_domain(AxonDomain::Local)
+                .cellular(CellScheme::pyramidal()
+                    .tft(TuftScheme::basal().distal()
+                        .dens_per_tft(2)
+                        .syns_per_den(32)
+                        .thresh_init(500)
+                        .src_lyr(TuftSourceLayer::define("iii")
+                            .syn_reach(20)
+                            .prevalence(1)
+                        )
+                    )
+                )
+            )
+        )
+        .lmap(LayerMapScheme::new("v0_lm", LayerMapKind::Subcortical)
+            .layer(LayerScheme::define("external_0")
+                .depth(1)
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::output(&[at_u0]))
+            )
+            .layer(LayerScheme::define("external_1")
+                .depth(1)
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::output(&[at_u1]))
+            )
+        )
+        .lmap(LayerMapScheme::new("m1_lm", LayerMapKind::Cortical)
+            .layer(LayerScheme::define("motor_ctx")
+                .axonal(AxonTopology::Nonspatial)
+                .axon_domain(AxonDomain::input(&[(InputTrack::Afferent, &[at_v1_out])]))
+            )
+            .layer(LayerScheme::define("unused")
+                .depth(1)
+                .tags(LayerTags::UNUSED)
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::Local)
+            )
+            .layer(LayerScheme::define("iii")
+                .depth(2)
+                .tags(LayerTags::PTAL)
+                .axon_domain(AxonDomain::output(&[AxonTag::unique()]))
+                .cellular(CellScheme::pyramidal()
+                    .tft(TuftScheme::basal().distal()
+                        .dens_per_tft(2)
+                        .syns_per_den(32)
+                        .thresh_init(500)
+                        .src_lyr(TuftSourceLayer::define("iii")
+                            .syn_reach(20)
+                            .prevalence(1)
+                        )
+                    )
+                    .tft(TuftScheme::apical().distal()
+                        .dens_per_tft(2)
+                        .syns_per_den(32)
+                        .thresh_init(500)
+                        .src_lyr(TuftSourceLayer::define("motor_ctx")
+                            .syn_reach(0)
+                            .prevalence(1)
+                        )
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iii_output")
+                .cellular(CellScheme::control(
+                        ControlCellKind::PyrOutputter {
+                            host_lyr_name: "iii".into(),
+                        },
+                        0
+                    )
+                )
+            )
+            .layer(LayerScheme::define("v")
+                .depth(1)
+                .axon_domain(AxonDomain::Local)
+                .cellular(CellScheme::pyramidal()
+                    .tft(TuftScheme::basal().distal()
+                        .dens_per_tft(2)
+                        .syns_per_den(32)
+                        .thresh_init(500)
+                        .src_lyr(TuftSourceLayer::define("iii")
+                            .syn_reach(20)
+                            .prevalence(1)
+                        )
+                    )
+                )
+            )
+        )
+}
+
+
+pub fn define_a_schemes() -> AreaSchemeList {
+    // ENCODE_SIZE: 64 --> range: (0.0, 172.0)
+    // ENCODE_SIZE: 32 --> range: (0.0, 76.0)
+    const ENCODE_SIZE: u32 = 32;
+    const AREA_SIDE: u32 = 48;
+
+    AreaSchemeList::new()
+        .area(AreaScheme::new("v0", "v0_lm", ENCODE_SIZE)
+            .encoder(EncoderScheme::ReversoScalarSequence { range: (0.0, 76.0), incr: 1.0 })
+        )
+        .area(AreaScheme::new("v1", "v1_lm", AREA_SIDE)
+            .eff_areas(vec!["v0"])
+        )
+        .area(AreaScheme::new("m1", "m1_lm", AREA_SIDE)
+            .eff_areas(vec!["v1"])
+        )
+}
+
+
+pub fn ca_settings() -> CorticalAreaSettings {
+    CorticalAreaSettings::new()
+        // .bypass_inhib()
+        // .bypass_filters()
+        // .disable_pyrs()
+        // .disable_ssts()
+        // .disable_mcols()
+        // .disable_regrowth()
+        // .disable_learning()
+}
@@ -0,0 +1,118 @@
+//! Scalar SDR gradient: encodes a scalar value as a sparse gradient.
+
+use bismit::CorticalAreaSettings;
+use bismit::map::*;
+use config::Config;
+
+
+pub fn config() -> Config {
+    Config::new(define_lm_schemes(), define_a_schemes(), ca_settings(),
+        vec!["v0".to_owned()], "v1".to_owned())
+}
+
+
+pub fn define_lm_schemes() -> LayerMapSchemeList {
+    let at0 = AxonTag::unique();
+
+    LayerMapSchemeList::new()
+        .lmap(LayerMapScheme::new("visual", LayerMapKind::Cortical)
+            .layer(LayerScheme::define("aff_in")
+                .axonal(AxonTopology::Nonspatial)
+                .axon_domain(AxonDomain::input(&[(InputTrack::Afferent, &[at0])]))
+            )
+            .layer(LayerScheme::define("iv")
+                .depth(1)
+                .tags(LayerTags::PSAL)
+                .axon_domain(AxonDomain::Local)
+                .cellular(CellScheme::spiny_stellate()
+                    .tft(TuftScheme::basal().proximal()
+                        .syns_per_den(32)
+                        .thresh_init(200)
+                        .src_lyr(TuftSourceLayer::define("aff_in")
+                            .syn_reach(16)
+                            .prevalence(1)
+                        )
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iv_inhib")
+                .cellular(CellScheme::control(
+                        ControlCellKind::InhibitoryBasketSurround {
+                            host_lyr_name: "iv".into(),
+                            field_radius: 4,
+                        },
+                        0
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iv_smooth")
+                .cellular(CellScheme::control(
+                        ControlCellKind::ActivitySmoother {
+                            host_lyr_name: "iv".into(),
+                            field_radius: 4,
+                        },
+                        1
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iii")
+                .depth(2)
+                .tags(LayerTags::PTAL)
+                .axon_domain(AxonDomain::output(&[AxonTag::unique()]))
+                .cellular(CellScheme::pyramidal()
+                    .tft(TuftScheme::basal().distal()
+                        .dens_per_tft(2)
+                        .syns_per_den(32)
+                        .thresh_init(200)
+                        .src_lyr(TuftSourceLayer::define("iii")
+                            .syn_reach(20)
+                            .prevalence(1)
+                        )
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iii_output")
+                .cellular(CellScheme::control(
+                        ControlCellKind::PyrOutputter {
+                            host_lyr_name: "iii".into(),
+                        },
+                        0
+                    )
+                )
+            )
+        )
+        .lmap(LayerMapScheme::new("v0_lm", LayerMapKind::Subcortical)
+            .layer(LayerScheme::define("external")
+                .depth(1)
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::output(&[at0]))
+            )
+        )
+}
+
+
+pub fn define_a_schemes() -> AreaSchemeList {
+    const ENCODE_SIZE: u32 = 32;
+    const AREA_SIDE: u32 = 16;
+
+    AreaSchemeList::new()
+        .area(AreaScheme::new("v0", "v0_lm", ENCODE_SIZE)
+            .encoder(EncoderScheme::ScalarSdrGradiant { range: (0.0, 10.0), way_span: 1.0,
+                incr: 1.0 })
+        )
+        .area(AreaScheme::new("v1", "visual", AREA_SIDE)
+            .eff_areas(vec!["v0"])
+        )
+}
+
+
+pub fn ca_settings() -> CorticalAreaSettings {
+    CorticalAreaSettings::new()
+        // .bypass_inhib()
+        // .bypass_filters()
+        // .disable_pyrs()
+        // .disable_ssts()
+        // .disable_mcols()
+        // .disable_regrowth()
+        // .disable_learning()
+}
@@ -0,0 +1,119 @@
+//! Scalar sequence: encodes a sequence of scalar values.
+
+use bismit::CorticalAreaSettings;
+use bismit::map::*;
+use config::Config;
+
+
+pub fn config() -> Config {
+    let aff_tag = AxonTag::unique();
+
+    Config::new(define_lm_schemes(aff_tag), define_a_schemes(aff_tag), ca_settings(),
+        vec!["v0".to_owned()], "v1".to_owned())
+}
+
+
+/// `aff_tag` connects the `v0` output to the `v1` input layer.
+pub fn define_lm_schemes(aff_tag: AxonTag) -> LayerMapSchemeList {
+    LayerMapSchemeList::new()
+        .lmap(LayerMapScheme::new("visual", LayerMapKind::Cortical)
+            .layer(LayerScheme::define("motor_ctx")
+                .axonal(AxonTopology::Nonspatial)
+                .axon_domain(AxonDomain::input(&[(InputTrack::Afferent, &[AxonTag::unique()])]))
+            )
+            .layer(LayerScheme::define("aff_in")
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::input(&[(InputTrack::Afferent, &[aff_tag])]))
+            )
+            .layer(LayerScheme::define("unused")
+                .depth(1)
+                .tags(LayerTags::UNUSED)
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::Local)
+            )
+            .layer(LayerScheme::define("iv")
+                .depth(1)
+                .tags(LayerTags::PSAL)
+                .axon_domain(AxonDomain::Local)
+                .cellular(CellScheme::spiny_stellate()
+                    .tft(TuftScheme::basal().proximal()
+                        .syns_per_den(128)
+                        .thresh_init(400)
+                        .src_lyr(TuftSourceLayer::define("aff_in")
+                            .syn_reach(12)
+                            .prevalence(1)
+                        )
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iv_inhib")
+                .cellular(CellScheme::control(
+                        ControlCellKind::InhibitoryBasketSurround {
+                            host_lyr_name: "iv".into(),
+                            field_radius: 4,
+                        },
+                        0
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iii")
+                .depth(2)
+                .tags(LayerTags::PTAL)
+                .axon_domain(AxonDomain::output(&[AxonTag::unique()]))
+                .cellular(CellScheme::pyramidal()
+                    .tft(TuftScheme::basal().distal()
+                        .dens_per_tft(2)
+                        .syns_per_den(64)
+                        .thresh_init(500)
+                        .src_lyr(TuftSourceLayer::define("iii")
+                            .syn_reach(14)
+                            .prevalence(1)
+                        )
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iii_output")
+                .cellular(CellScheme::control(
+                        ControlCellKind::PyrOutputter {
+                            host_lyr_name: "iii".into(),
+                        },
+                        0
+                    )
+                )
+            )
+        )
+        .lmap(LayerMapScheme::new("v0_lm", LayerMapKind::Subcortical)
+            .layer(LayerScheme::define("external")
+                .depth(1)
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::output(&[aff_tag]))
+            )
+        )
+}
+
+
+pub fn define_a_schemes(aff_tag: AxonTag) -> AreaSchemeList {
+    const ENCODE_SIZE: u32 = 64;
+    const AREA_SIDE: u32 = 48;
+
+    AreaSchemeList::new()
+        .area(AreaScheme::new("v0", "v0_lm", ENCODE_SIZE)
+            .encoder(EncoderScheme::ScalarSequence { range: (-8.0, 8.0), incr: 0.03 })
+        )
+        .area(AreaScheme::new("v1", "visual", AREA_SIDE)
+            .eff_areas(vec!["v0"])
+            .filter_chain(InputTrack::Afferent, &[aff_tag], &[("retina", None)])
+        )
+}
+
+
+pub fn ca_settings() -> CorticalAreaSettings {
+    CorticalAreaSettings::new()
+        // .bypass_inhib()
+        // .bypass_filters()
+        // .disable_pyrs()
+        // .disable_ssts()
+        // .disable_mcols()
+        // .disable_regrowth()
+        // .disable_learning()
+}
@@ -0,0 +1,123 @@
+//! Skewed hex: an irregular (non-square) input area feeding a large area.
+//!
+//! The original example drove `v0` with a `HexMoldTest` encoder attached
+//! directly to the thalamic pathway. Encoders are now created by
+//! `InputGenerator` from the area scheme, so a scalar gradient is used
+//! instead.
+
+use bismit::CorticalAreaSettings;
+use bismit::map::*;
+use config::Config;
+
+const DST_AREA_SCL: u32 = 8;
+const AREA_SIDE: u32 = 16 * DST_AREA_SCL;
+
+
+pub fn config() -> Config {
+    Config::new(define_lm_schemes(), define_a_schemes(), ca_settings(),
+        vec!["v0".to_owned()], "v1".to_owned())
+}
+
+
+pub fn define_lm_schemes() -> LayerMapSchemeList {
+    let at0 = AxonTag::unique();
+
+    LayerMapSchemeList::new()
+        .lmap(LayerMapScheme::new("v1_lm", LayerMapKind::Cortical)
+            .layer(LayerScheme::define("motor_ctx")
+                .axonal(AxonTopology::Nonspatial)
+                .axon_domain(AxonDomain::input(&[(InputTrack::Afferent, &[AxonTag::unique()])]))
+            )
+            .layer(LayerScheme::define("aff_in")
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::input(&[(InputTrack::Afferent, &[at0])]))
+            )
+            .layer(LayerScheme::define("unused")
+                .depth(1)
+                .tags(LayerTags::UNUSED)
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::Local)
+            )
+            .layer(LayerScheme::define("iv")
+                .depth(1)
+                .tags(LayerTags::PSAL)
+                .axon_domain(AxonDomain::Local)
+                .cellular(CellScheme::spiny_stellate()
+                    .tft(TuftScheme::basal().proximal()
+                        .syns_per_den(64)
+                        .thresh_init(0)
+                        .src_lyr(TuftSourceLayer::define("aff_in")
+                            .syn_reach(10)
+                            .prevalence(1)
+                        )
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iv_inhib")
+                .cellular(CellScheme::control(
+                        ControlCellKind::InhibitoryBasketSurround {
+                            host_lyr_name: "iv".into(),
+                            field_radius: 4,
+                        },
+                        0
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iii")
+                .depth(1)
+                .tags(LayerTags::PTAL)
+                .axon_domain(AxonDomain::output(&[AxonTag::unique()]))
+                .cellular(CellScheme::pyramidal()
+                    .tft(TuftScheme::basal().distal()
+                        .dens_per_tft(2)
+                        .syns_per_den(8)
+                        .thresh_init(500)
+                        .src_lyr(TuftSourceLayer::define("iii")
+                            .syn_reach(10)
+                            .prevalence(1)
+                        )
+                    )
+                )
+            )
+            .layer(LayerScheme::define("iii_output")
+                .cellular(CellScheme::control(
+                        ControlCellKind::PyrOutputter {
+                            host_lyr_name: "iii".into(),
+                        },
+                        0
+                    )
+                )
+            )
+        )
+        .lmap(LayerMapScheme::new("v0_lm", LayerMapKind::Subcortical)
+            .layer(LayerScheme::define("external")
+                .depth(1)
+                .axonal(AxonTopology::Spatial)
+                .axon_domain(AxonDomain::output(&[at0]))
+            )
+        )
+}
+
+
+pub fn define_a_schemes() -> AreaSchemeList {
+    AreaSchemeList::new()
+        .area(AreaScheme::irregular("v0", "v0_lm", [64, 64])
+            .encoder(EncoderScheme::ScalarSdrGradiant { range: (0.0, 10.0), way_span: 1.0,
+                incr: 1.0 })
+        )
+        .area(AreaScheme::new("v1", "v1_lm", AREA_SIDE)
+            .eff_areas(vec!["v0"])
+        )
+}
+
+
+pub fn ca_settings() -> CorticalAreaSettings {
+    CorticalAreaSettings::new()
+        .bypass_inhib()
+        .bypass_filters()
+        .disable_pyrs()
+        // .disable_ssts()
+        // .disable_mcols()
+        // .disable_regrowth()
+        // .disable_learning()
+}
@@ -0,0 +1,28 @@
+//! Descriptions of the areas of a cortex.
+
+use bismit::Cortex;
+use window::{TractLayout, CellLayerInfo, cell_layers};
+
+
+/// The name, tract layout, and cell layers of a cortical area.
+///
+/// Flywheels only provide `AreaInfo` for the area they were created with, so
+/// these are gathered from the cortex before it is handed off.
+#[derive(Clone, Debug)]
+pub struct AreaDesc {
+    pub name: String,
+    pub layout: TractLayout,
+    pub cell_layers: Vec<CellLayerInfo>,
+}
+
+
+/// Describes every area within a cortex.
+pub fn describe_areas(cortex: &Cortex) -> Vec<AreaDesc> {
+    cortex.areas().iter().map(|area| {
+        AreaDesc {
+            name: area.name().to_owned(),
+            layout: TractLayout::from_tract_map(&area.axn_tract_map()),
+            cell_layers: cell_layers(cortex, area.name()),
+        }
+    }).collect()
+}
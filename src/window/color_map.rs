@@ -0,0 +1,345 @@
+//! Color maps and value scaling used to display axon states.
+//!
+//! Each color map is sampled into a row of a lookup texture (see
+//! `color_maps_texture`) which the hex grid shader indexes after scaling a
+//! state according to a `ValueScale`. The same tables are used to draw the
+//! on-screen legend.
+
+use glium::Surface;
+use glium::backend::glutin::Display;
+use glium::texture::{RawImage2d, Texture2d};
+use window::Overlay;
+use window::overlay::{TEXT_SIZE, TEXT_COLOR};
+use window::history::{DIFF_STAYED_OFF, DIFF_TURNED_OFF, DIFF_STAYED_ON, DIFF_TURNED_ON};
+
+/// Number of entries in each color map lookup table (one per `u8` state).
+pub const TABLE_LEN: usize = 256;
+
+/// States at or above this are considered active by `ValueScale::Threshold`
+/// by default.
+pub const DEFAULT_THRESHOLD: u8 = 128;
+
+const LEGEND_BAR_SIZE: (f32, f32) = (192.0, 12.0);
+const LEGEND_BAR_STEPS: usize = 64;
+const LEGEND_PADDING: f32 = 6.0;
+const LEGEND_BG_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const LEGEND_TICK_SIZE: f32 = TEXT_SIZE * 0.8;
+
+
+/// A mapping of (scaled) values within [0.0, 1.0] to colors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ColorMap {
+    /// The original red (active) on blue (inactive) mapping, boosting any
+    /// non-zero state to at least half intensity.
+    Classic,
+    Viridis,
+    Grayscale,
+    /// Active (non-zero) or inactive only.
+    Binary,
+    /// Blue through white to red, centered on 0.5. Best used with
+    /// `ValueScale::Signed`.
+    Diverging,
+    /// Distinct colors for each kind of change displayed in
+    /// `DisplayMode::Difference`. Not selectable.
+    Difference,
+}
+
+impl ColorMap {
+    pub fn all() -> [ColorMap; 6] {
+        [ColorMap::Classic, ColorMap::Viridis, ColorMap::Grayscale, ColorMap::Binary,
+            ColorMap::Diverging, ColorMap::Difference]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ColorMap::Classic => "classic",
+            ColorMap::Viridis => "viridis",
+            ColorMap::Grayscale => "grayscale",
+            ColorMap::Binary => "binary",
+            ColorMap::Diverging => "diverging",
+            ColorMap::Difference => "difference",
+        }
+    }
+
+    /// Returns the selectable color map following this one (wrapping
+    /// around).
+    pub fn next(&self) -> ColorMap {
+        match *self {
+            ColorMap::Classic => ColorMap::Viridis,
+            ColorMap::Viridis => ColorMap::Grayscale,
+            ColorMap::Grayscale => ColorMap::Binary,
+            ColorMap::Binary => ColorMap::Diverging,
+            ColorMap::Diverging | ColorMap::Difference => ColorMap::Classic,
+        }
+    }
+
+    /// Row of this color map within the lookup texture.
+    pub fn idx(&self) -> usize {
+        *self as usize
+    }
+
+    /// Returns the color of a value within [0.0, 1.0].
+    pub fn color(&self, t: f32) -> [f32; 3] {
+        let t = t.max(0.0).min(1.0);
+
+        match *self {
+            ColorMap::Classic => {
+                // Matches `u_global_color` as it was (0.0, 0.0, 0.3):
+                let c = if t > 0.0002 { t * 0.5 + 0.5 } else { 0.0 };
+                [c, 0.0, 0.3 - 0.3 * c]
+            },
+            ColorMap::Viridis => viridis(t),
+            ColorMap::Grayscale => {
+                let c = 0.05 + 0.95 * t;
+                [c, c, c]
+            },
+            ColorMap::Binary => {
+                if t > 0.0002 { [1.0, 0.85, 0.2] } else { [0.05, 0.05, 0.12] }
+            },
+            ColorMap::Diverging => {
+                let (lo, mid, hi) = ([0.23, 0.30, 0.75], [0.87, 0.87, 0.87], [0.71, 0.02, 0.15]);
+                if t < 0.5 { lerp(lo, mid, t * 2.0) } else { lerp(mid, hi, (t - 0.5) * 2.0) }
+            },
+            ColorMap::Difference => {
+                // Nearest of the `DIFF_*` values:
+                match (t * 255.0).round() as u8 {
+                    v if v < DIFF_STAYED_OFF / 2 + DIFF_TURNED_OFF / 2 => [0.04, 0.05, 0.10],
+                    v if v < DIFF_TURNED_OFF / 2 + DIFF_STAYED_ON / 2 => [0.15, 0.40, 1.00],
+                    v if v < DIFF_STAYED_ON / 2 + DIFF_TURNED_ON / 2 => [0.55, 0.32, 0.08],
+                    _ => [1.00, 0.92, 0.25],
+                }
+            },
+        }
+    }
+
+    /// Returns the lookup table for this color map.
+    pub fn table(&self) -> Vec<[f32; 3]> {
+        (0..TABLE_LEN).map(|i| self.color(i as f32 / (TABLE_LEN - 1) as f32)).collect()
+    }
+}
+
+
+/// The conversion of a raw `u8` state to a value within [0.0, 1.0] before it
+/// is color mapped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ValueScale {
+    Linear,
+    /// Logarithmic, making low states easier to tell apart.
+    Log,
+    /// 1.0 for states at or above the threshold, 0.0 otherwise.
+    Threshold(u8),
+    /// States interpreted as `i8` (synapse strengths, etc.), with 0 at 0.5.
+    Signed,
+}
+
+impl ValueScale {
+    pub fn name(&self) -> String {
+        match *self {
+            ValueScale::Linear => "linear".to_owned(),
+            ValueScale::Log => "log".to_owned(),
+            ValueScale::Threshold(t) => format!("threshold {}", t),
+            ValueScale::Signed => "signed".to_owned(),
+        }
+    }
+
+    /// Returns the scale following this one (wrapping around).
+    pub fn next(&self) -> ValueScale {
+        match *self {
+            ValueScale::Linear => ValueScale::Log,
+            ValueScale::Log => ValueScale::Threshold(DEFAULT_THRESHOLD),
+            ValueScale::Threshold(_) => ValueScale::Signed,
+            ValueScale::Signed => ValueScale::Linear,
+        }
+    }
+
+    /// Identifies the scale within the shader (`u_scale`).
+    pub fn shader_id(&self) -> i32 {
+        match *self {
+            ValueScale::Linear => 0,
+            ValueScale::Log => 1,
+            ValueScale::Threshold(_) => 2,
+            ValueScale::Signed => 3,
+        }
+    }
+
+    /// The threshold passed to the shader (`u_threshold`).
+    pub fn threshold(&self) -> f32 {
+        match *self {
+            ValueScale::Threshold(t) => t as f32,
+            _ => 0.0,
+        }
+    }
+
+    /// Scales a state as the shader does.
+    pub fn apply(&self, state: u8) -> f32 {
+        match *self {
+            ValueScale::Linear => state as f32 / 255.0,
+            ValueScale::Log => (1.0 + state as f32).ln() / 256.0f32.ln(),
+            ValueScale::Threshold(t) => if state >= t { 1.0 } else { 0.0 },
+            ValueScale::Signed => (state as i8 as f32 + 128.0) / 255.0,
+        }
+    }
+
+    /// Labels for the low, middle, and high ends of the legend.
+    fn tick_labels(&self) -> [String; 3] {
+        match *self {
+            ValueScale::Linear => ["0".to_owned(), "128".to_owned(), "255".to_owned()],
+            ValueScale::Log => ["0".to_owned(), "15".to_owned(), "255".to_owned()],
+            ValueScale::Threshold(t) => [format!("< {}", t), String::new(), format!(">= {}", t)],
+            ValueScale::Signed => ["-128".to_owned(), "0".to_owned(), "127".to_owned()],
+        }
+    }
+}
+
+
+/// How the states of a slice are displayed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Encoding {
+    pub color_map: ColorMap,
+    pub scale: ValueScale,
+}
+
+impl Encoding {
+    pub fn new(color_map: ColorMap, scale: ValueScale) -> Encoding {
+        Encoding { color_map: color_map, scale: scale }
+    }
+
+    pub fn name(&self) -> String {
+        format!("{} / {}", self.color_map.name(), self.scale.name())
+    }
+}
+
+/// The encoding used by `DisplayMode::Difference`.
+pub const DIFFERENCE_ENCODING: Encoding = Encoding {
+    color_map: ColorMap::Difference,
+    scale: ValueScale::Linear,
+};
+
+impl Default for Encoding {
+    fn default() -> Encoding {
+        Encoding::new(ColorMap::Classic, ValueScale::Linear)
+    }
+}
+
+
+/// Creates a texture containing the lookup table of every color map, one
+/// per row (see `ColorMap::idx`).
+pub fn color_maps_texture(display: &Display) -> Texture2d {
+    let maps = ColorMap::all();
+    let data: Vec<f32> = maps.iter()
+        .flat_map(|map| map.table().into_iter().flat_map(|c| c.to_vec().into_iter()))
+        .collect();
+
+    let image = RawImage2d::from_raw_rgb(data, (TABLE_LEN as u32, maps.len() as u32));
+    Texture2d::new(display, image).unwrap()
+}
+
+
+/// Draws a legend titled `title` (describing what is displayed), with one
+/// color bar per encoding along with the layers using it, with its bottom
+/// left corner at `pos`.
+pub fn draw_legend<S: Surface>(overlay: &Overlay, target: &mut S, pos: (f32, f32),
+        title: &str, entries: &[(Encoding, String)])
+{
+    if entries.is_empty() { return; }
+
+    let line_height = TEXT_SIZE * 1.4;
+    let entry_height = |enc: &Encoding| if enc.color_map == ColorMap::Difference {
+        line_height * 5.0
+    } else {
+        line_height + LEGEND_BAR_SIZE.1 + line_height
+    };
+
+    let width = entries.iter().map(|&(ref enc, ref layers)| {
+        overlay.text_width(&format!("{}: {}", enc.name(), layers), TEXT_SIZE)
+    }).fold(LEGEND_BAR_SIZE.0.max(overlay.text_width(title, TEXT_SIZE)), |a, b| a.max(b))
+        + LEGEND_PADDING * 2.0;
+    let height = line_height + entries.iter().map(|e| entry_height(&e.0)).sum::<f32>()
+        + LEGEND_PADDING * 2.0;
+
+    let top = pos.1 - height;
+    overlay.draw_rect(target, (pos.0, top), (width, height), LEGEND_BG_COLOR);
+    overlay.draw_text(target, title, (pos.0 + LEGEND_PADDING,
+        top + LEGEND_PADDING + line_height * 0.7), TEXT_SIZE, TEXT_COLOR);
+
+    let x = pos.0 + LEGEND_PADDING;
+    let mut y = top + LEGEND_PADDING + line_height;
+
+    for &(ref enc, ref layers) in entries.iter() {
+        overlay.draw_text(target, &format!("{}: {}", enc.name(), layers),
+            (x, y + line_height * 0.7), TEXT_SIZE, TEXT_COLOR);
+
+        if enc.color_map == ColorMap::Difference {
+            draw_difference_key(overlay, target, (x, y + line_height), line_height);
+        } else {
+            draw_color_bar(overlay, target, (x, y + line_height), line_height, enc);
+        }
+        y += entry_height(enc);
+    }
+}
+
+/// Draws a color bar (sampled in scaled value space) with raw state ticks
+/// beneath it.
+fn draw_color_bar<S: Surface>(overlay: &Overlay, target: &mut S, pos: (f32, f32),
+        line_height: f32, enc: &Encoding)
+{
+    let (x, bar_y) = pos;
+    let step_width = LEGEND_BAR_SIZE.0 / LEGEND_BAR_STEPS as f32;
+    for step in 0..LEGEND_BAR_STEPS {
+        let t = step as f32 / (LEGEND_BAR_STEPS - 1) as f32;
+        let c = enc.color_map.color(t);
+        overlay.draw_rect(target, (x + step_width * step as f32, bar_y),
+            (step_width + 0.5, LEGEND_BAR_SIZE.1), [c[0], c[1], c[2], 1.0]);
+    }
+
+    let tick_y = bar_y + LEGEND_BAR_SIZE.1 + line_height * 0.8;
+    let labels = enc.scale.tick_labels();
+    let widths: Vec<f32> = labels.iter()
+        .map(|l| overlay.text_width(l, LEGEND_TICK_SIZE)).collect();
+    overlay.draw_text(target, &labels[0], (x, tick_y), LEGEND_TICK_SIZE, TEXT_COLOR);
+    overlay.draw_text(target, &labels[1], (x + (LEGEND_BAR_SIZE.0 - widths[1]) / 2.0, tick_y),
+        LEGEND_TICK_SIZE, TEXT_COLOR);
+    overlay.draw_text(target, &labels[2], (x + LEGEND_BAR_SIZE.0 - widths[2], tick_y),
+        LEGEND_TICK_SIZE, TEXT_COLOR);
+}
+
+/// Draws a swatch and label for each kind of change.
+fn draw_difference_key<S: Surface>(overlay: &Overlay, target: &mut S, pos: (f32, f32),
+        line_height: f32)
+{
+    let kinds = [(DIFF_TURNED_ON, "turned on"), (DIFF_TURNED_OFF, "turned off"),
+        (DIFF_STAYED_ON, "stayed on"), (DIFF_STAYED_OFF, "stayed off")];
+
+    for (i, &(value, label)) in kinds.iter().enumerate() {
+        let y = pos.1 + line_height * i as f32;
+        let c = ColorMap::Difference.color(value as f32 / 255.0);
+        overlay.draw_rect(target, (pos.0, y + line_height * 0.15),
+            (LEGEND_BAR_SIZE.1, LEGEND_BAR_SIZE.1), [c[0], c[1], c[2], 1.0]);
+        overlay.draw_text(target, label, (pos.0 + LEGEND_BAR_SIZE.1 * 2.0, y + line_height * 0.7),
+            LEGEND_TICK_SIZE, TEXT_COLOR);
+    }
+}
+
+
+/// Polynomial approximation of the viridis color map.
+fn viridis(t: f32) -> [f32; 3] {
+    const C: [[f32; 3]; 7] = [
+        [0.2777273272234177, 0.005407344544966578, 0.3340998053353061],
+        [0.1050930431085774, 1.404613529898575, 1.384590162594685],
+        [-0.3308618287255563, 0.214847559468213, 0.09509516302823659],
+        [-4.634230498983486, -5.799100973351585, -19.33244095627987],
+        [6.228269936347081, 14.17993336680509, 56.69055260068105],
+        [4.776384997670288, -13.74514537774601, -65.35303263337234],
+        [-5.435455855934631, 4.645852612178535, 26.3124352495832],
+    ];
+
+    let mut c = [0.0f32; 3];
+    for ch in 0..3 {
+        c[ch] = C.iter().rev().fold(0.0, |acc, coeffs| acc * t + coeffs[ch]).max(0.0).min(1.0);
+    }
+    c
+}
+
+fn lerp(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t, a[2] + (b[2] - a[2]) * t]
+}
@@ -2,23 +2,53 @@
 // use std::sync::mpsc::Sender;
 // use glium::backend::glutin::{GlutinFacade};
 use glium::backend::glutin::Display;
-use glium::{self, Surface, Program, DrawParameters, VertexBuffer, IndexBuffer};
+use std::ops::Range;
+use glium::{self, Surface, Program, DrawParameters, VertexBuffer, IndexBuffer, Rect};
+use glium::texture::Texture2d;
+use glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter, SamplerWrapFunction};
 // use glium::glutin::{ElementState, MouseButton};
 // use vecmath;
 // use enamel::MouseState;
-use bismit::flywheel::AreaInfo;
-use bismit::map::SliceTractMap;
-use window::HexGridBuffer;
+use window::{HexGridBuffer, TractLayout, Encoding, ColorMap, DisplayMode};
+use window::color_map;
 
 const CAM_Z_NEAR: f32 = -0.4;
 const CAM_Z_FAR: f32 = 1.4;
 
+const CAM_DEFAULT_DST: f32 = 1.5;
+// Camera distance multiplier per unit of normalized zoom:
+const CAM_ZOOM_BASE: f32 = 1.25;
+// Space left around the visible slices when fitting them to the view:
+const CAM_FIT_MARGIN: f32 = 1.05;
+// Divisor of pi giving the field of view (see `persp_matrix`):
+const FOV_ZOOM: f32 = 3.0;
+// Orbit rotation (radians) per pixel of mouse movement:
+const CAM_ORBIT_RATE: f32 = 0.005;
+// Maximum tilt (radians) above or below the slice plane's normal:
+const CAM_PITCH_MAX: f32 = 1.4;
+// Distance between the planes of adjacent stacked slices:
+const SLC_Z_SPACING: f32 = 25.0;
+
 const HEX_X: f32 = 0.086602540378 + 0.01;
 const HEX_Y: f32 = 0.05 + 0.01;
 
 const TEXT_SCALE: f32 = 0.018;
 const TEXT_COLOR: (f32, f32, f32, f32) = (0.99, 0.99, 0.99, 1.0);
 
+// Distance from a hexagon's center to its corners (see `hex_vbo`):
+const HEX_RADIUS: f32 = 0.57735026919 / 10.0;
+
+
+/// The hexagon (axon) found under a point on the screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HexPick {
+    pub slc_id: usize,
+    pub v_id: u32,
+    pub u_id: u32,
+    /// Index of the axon within the area's axon space.
+    pub axn_idx: usize,
+}
+
 
 pub struct HexGrid<'d> {
     vertices: VertexBuffer<Vertex>,
@@ -27,18 +57,26 @@ pub struct HexGrid<'d> {
     params: DrawParameters<'d>,
     pub cam_pos_norm: [f32; 3],
     pub cam_pos_raw: [f32; 3],
+    /// Orbit rotation around the framed point (yaw, pitch) in radians.
+    cam_orbit: [f32; 2],
+    /// Look direction, derived from `cam_orbit`.
+    cam_dir: [f32; 3],
     model_plane_size: [f32; 2],
     viewable_plane_size: [f32; 2],
     pub buffer: HexGridBuffer,
     being_dragged: bool,
     surface_dims: (u32, u32),
+    window_dims: (u32, u32),
+    viewport: Option<Rect>,
     light_pos: [f32; 3],
-    global_color: [f32; 3],
+    color_maps: Texture2d,
+    /// Encoding of each slice, by slice id.
+    encodings: Vec<Encoding>,
     pub top_right_scene: [f32; 4],
 }
 
 impl<'d> HexGrid<'d> {
-    pub fn new(display: &Display, area_info: AreaInfo) -> HexGrid {
+    pub fn new(display: &Display, layout: TractLayout) -> HexGrid {
         // The greatest hexagon ever made (o rly?):
         let vertices = hex_vbo(display);
         let indices = hex_ibo(display);
@@ -58,23 +96,26 @@ impl<'d> HexGrid<'d> {
             .. Default::default()
         };
 
-        let buffer = HexGridBuffer::new(area_info, &display);
-        let default_cam_dst = 1.5f32;
-
+        let buffer = HexGridBuffer::new(layout, &display);
         let mut hg = HexGrid {
             vertices: vertices,
             indices: indices,
             program: program,
             params: params,
-            cam_pos_norm: [0.0, 0.0, default_cam_dst],
+            cam_pos_norm: [0.0, 0.0, CAM_DEFAULT_DST],
             cam_pos_raw: [0.0, 0.0, -1.0],
+            cam_orbit: [0.0, 0.0],
+            cam_dir: [0.0, 0.0, 1.0],
             model_plane_size: [0.0, 0.0],
             viewable_plane_size: [0.0, 0.0],
             buffer: buffer,
             being_dragged: false,
             surface_dims: display.get_framebuffer_dimensions(),
+            window_dims: display.get_framebuffer_dimensions(),
+            viewport: None,
             light_pos: [-1.0, 0.4, -0.9f32],
-            global_color: [0.0, 0.0, 0.3f32],
+            color_maps: color_map::color_maps_texture(display),
+            encodings: Vec::new(),
             top_right_scene: [0.0; 4],
         };
         hg.update_cam_pos();
@@ -85,43 +126,27 @@ impl<'d> HexGrid<'d> {
         // Set up our frame-countery-thing:
         let f_c = (elapsed_ms * 0.00025) as f32;
 
-        // Get frame dimensions:
-        self.surface_dims = target.get_dimensions();
+        // Get frame (or viewport) dimensions:
+        self.set_window_dims(target.get_dimensions());
 
         // Perspective transformation matrix:
-        let persp = persp_matrix(self.surface_dims.0, self.surface_dims.1, 3.0);
+        let persp = self.persp();
 
-        // View transformation matrix: { position(x,y,z), direction(x,y,z), up_dim(x,y,z)}
-        let view = view_matrix(&self.cam_pos_raw, &[0.0, 0.0, 1.0], &[0.0, 1.0, 0.0]);
-
-        let slc_count = self.buffer.cur_slc_range().len();
-        // self.model_plane_size = [0.0, 0.0];
+        // View transformation matrix:
+        let view = self.view();
 
         // Loop through currently visible slices:
-        for slc_id in self.buffer.cur_slc_range().clone() {
-            let grid_dims = self.buffer.tract_map().slc_dims(slc_id as u8);
-
-            // let x_size = (grid_dims.0 + grid_dims.1) as f32 * HEX_X;
-            let y_size = (grid_dims.0 + grid_dims.1) as f32 * HEX_Y;
-
-            let scl = 100.0 / y_size;
-
-            let slc_idm = self.buffer.cur_slc_range().end - 1;
-
-            // Set up model position:
-            // let x_shift = 18.0 * slc_count as f32 * (slc_idm - slc_id) as f32;
-            // let y_shift = 10.0 * slc_count as f32 * (slc_idm - slc_id) as f32;
-            let x_shift = 90.0 * (slc_idm - slc_id) as f32;
-            let y_shift = 50.0 * (slc_idm - slc_id) as f32;
-            let z_shift = 1.0;
+        for &slc_id in self.buffer.visible_slcs() {
+            let grid_dims = self.buffer.layout().slc_dims(slc_id);
 
             // Model transformation matrix:
-            let model = [
-                [scl, 0.0, 0.0, 0.0],
-                [0.0, scl, 0.0, 0.0],
-                [0.0, 0.0, 1.0, 0.0],
-                [x_shift, y_shift, z_shift, 1.0f32]
-            ];
+            let model = self.slc_model(slc_id);
+
+            let encoding = self.display_encoding(slc_id);
+            let color_maps = self.color_maps.sampled()
+                .magnify_filter(MagnifySamplerFilter::Nearest)
+                .minify_filter(MinifySamplerFilter::Nearest)
+                .wrap_function(SamplerWrapFunction::Clamp);
 
             // Uniforms:
             let uniforms = uniform! {
@@ -129,36 +154,287 @@ impl<'d> HexGrid<'d> {
                 view: view,
                 persp: persp,
                 u_light_pos: self.light_pos,
-                u_global_color: self.global_color,
+                u_color_maps: color_maps,
+                u_color_map: encoding.color_map.idx() as f32,
+                u_color_map_count: ColorMap::all().len() as f32,
+                u_scale: encoding.scale.shader_id(),
+                u_threshold: encoding.scale.threshold(),
                 grid_v_size: grid_dims.0,
                 grid_u_size: grid_dims.1,
             };
 
             // Draw Grid (with per-instance vertex buffer):
-            target.draw((&self.vertices, self.buffer.raw_states_buf(slc_id as u8)
-                .per_instance().unwrap()), &self.indices, &self.program, &uniforms,
-                &self.params).unwrap();
+            target.draw((&self.vertices,
+                    self.buffer.display_states_buf(slc_id as u8).per_instance().unwrap(),
+                    self.buffer.highlights_buf(slc_id as u8).per_instance().unwrap()),
+                &self.indices, &self.program, &uniforms, &self.params).unwrap();
         }
     }
 
-    // [TODO]: Simplify this mess and try to get it more accurate.
-    pub fn update_cam_pos(&mut self) {
-        let aspect_ratio = self.surface_dims.1 as f32 / self.surface_dims.0 as f32;
-        let slc_count = self.buffer.cur_slc_range().len() as f32;
+    /// Returns the encoding used to display a slice.
+    pub fn encoding(&self, slc_id: usize) -> Encoding {
+        self.encodings.get(slc_id).cloned().unwrap_or_default()
+    }
+
+    /// Returns the encoding actually used to display a slice, which depends
+    /// on the display mode.
+    fn display_encoding(&self, slc_id: usize) -> Encoding {
+        match self.buffer.display_mode() {
+            DisplayMode::Difference => color_map::DIFFERENCE_ENCODING,
+            _ => self.encoding(slc_id),
+        }
+    }
+
+    /// Sets the encoding used to display a range of slices.
+    pub fn set_encoding(&mut self, slc_range: Range<usize>, encoding: Encoding) {
+        if self.encodings.len() < slc_range.end {
+            self.encodings.resize(slc_range.end, Encoding::default());
+        }
+        for slc_id in slc_range {
+            self.encodings[slc_id] = encoding;
+        }
+    }
+
+    /// Returns each distinct encoding of the visible slices along with the
+    /// tags of the layers using it.
+    pub fn visible_encodings(&self) -> Vec<(Encoding, String)> {
+        let mut entries: Vec<(Encoding, String)> = Vec::new();
+
+        for (tags, slc_range) in self.buffer.layout().layers() {
+            if !slc_range.clone().any(|slc_id| self.buffer.is_slc_visible(slc_id)) { continue; }
+            let encoding = self.display_encoding(slc_range.start);
+
+            match entries.iter().position(|e| e.0 == encoding) {
+                Some(idx) => {
+                    entries[idx].1.push_str(", ");
+                    entries[idx].1.push_str(tags);
+                },
+                None => entries.push((encoding, tags.to_owned())),
+            }
+        }
+        entries
+    }
+
+    /// Restricts drawing to a region of the window (`None` for the whole
+    /// window).
+    pub fn set_viewport(&mut self, viewport: Option<Rect>) {
+        self.viewport = viewport;
+        self.params.viewport = viewport;
+        let window_dims = self.window_dims;
+        self.set_window_dims(window_dims);
+    }
+
+    pub fn viewport(&self) -> Option<Rect> {
+        self.viewport
+    }
+
+    /// Converts a window position (as reported by mouse events) to a
+    /// position within the viewport, if it lies within it.
+    pub fn viewport_pos(&self, pos: (i32, i32)) -> Option<(i32, i32)> {
+        let (x, y) = match self.viewport {
+            // Viewports are measured from the bottom left:
+            Some(ref vp) => (pos.0 - vp.left as i32,
+                pos.1 - (self.window_dims.1 as i32 - (vp.bottom + vp.height) as i32)),
+            None => pos,
+        };
 
-        let x_ofs = 225.;
-        let cam_x_pos = self.cam_pos_norm[0].mul_add(-1000.0, x_ofs);
+        if x >= 0 && y >= 0 && x < self.surface_dims.0 as i32 && y < self.surface_dims.1 as i32 {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the perspective transformation matrix.
+    fn persp(&self) -> [[f32; 4]; 4] {
+        persp_matrix(self.surface_dims.0, self.surface_dims.1, FOV_ZOOM)
+    }
+
+    /// Returns the view transformation matrix.
+    fn view(&self) -> [[f32; 4]; 4] {
+        // { position(x,y,z), direction(x,y,z), up_dim(x,y,z)}
+        view_matrix(&self.cam_pos_raw, &self.cam_dir, &[0.0, 1.0, 0.0])
+    }
+
+    /// Returns the model transformation matrix for a slice.
+    fn slc_model(&self, slc_id: usize) -> [[f32; 4]; 4] {
+        let grid_dims = self.buffer.layout().slc_dims(slc_id);
+
+        // let x_size = (grid_dims.0 + grid_dims.1) as f32 * HEX_X;
+        let y_size = (grid_dims.0 + grid_dims.1) as f32 * HEX_Y;
+
+        let scl = 100.0 / y_size;
+
+        // Stack visible slices without gaps (the last slice is in front,
+        // nearest the unrotated camera):
+        let visible_slcs = self.buffer.visible_slcs();
+        let stack_pos = visible_slcs.len() - 1 - visible_slcs.iter()
+            .position(|&id| id == slc_id).unwrap_or(visible_slcs.len() - 1);
+
+        // Set up model position:
+        // let x_shift = 18.0 * slc_count as f32 * (slc_idm - slc_id) as f32;
+        // let y_shift = 10.0 * slc_count as f32 * (slc_idm - slc_id) as f32;
+        let x_shift = 90.0 * stack_pos as f32;
+        let y_shift = 50.0 * stack_pos as f32;
+        let z_shift = 1.0 + SLC_Z_SPACING * stack_pos as f32;
+
+        [
+            [scl, 0.0, 0.0, 0.0],
+            [0.0, scl, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [x_shift, y_shift, z_shift, 1.0f32]
+        ]
+    }
+
+    /// Returns the hexagon under the screen position `pos` (in pixels, as
+    /// reported by mouse events), if any.
+    ///
+    /// Casts a ray from the camera through `pos` and intersects it with the
+    /// plane of each visible slice, returning the hit nearest the camera.
+    pub fn pick(&self, pos: (i32, i32)) -> Option<HexPick> {
+        let (width, height) = self.surface_dims;
+        if width == 0 || height == 0 { return None; }
+
+        let pos = match self.viewport_pos(pos) {
+            Some(pos) => pos,
+            None => return None,
+        };
+
+        let ndc_x = (pos.0 as f32 / width as f32) * 2.0 - 1.0;
+        let ndc_y = 1.0 - (pos.1 as f32 / height as f32) * 2.0;
+
+        let (near, far) = screen_ray(&self.persp(), &self.view(), (ndc_x, ndc_y))?;
+
+        let mut nearest: Option<(f32, HexPick)> = None;
+
+        for &slc_id in self.buffer.visible_slcs() {
+            let grid_dims = self.buffer.layout().slc_dims(slc_id);
+
+            if let Some((t, (v_id, u_id))) = ray_hex(near, far, &self.slc_model(slc_id),
+                    grid_dims)
+            {
+                if nearest.map(|(t_min, _)| t >= t_min).unwrap_or(false) { continue; }
 
-        let y_ofs = 120.;
-        let cam_y_pos = self.cam_pos_norm[1].mul_add(1000.0, y_ofs);
+                let axn_idx = self.buffer.layout()
+                    .axn_id_range(slc_id..(slc_id + 1)).start
+                    + (v_id * grid_dims.1 + u_id) as usize;
 
-        let z_ofs = -0.01 + -200.;
-        let cam_z_pos = self.cam_pos_norm[2].mul_add(-53.0, z_ofs);
+                nearest = Some((t, HexPick { slc_id: slc_id, v_id: v_id, u_id: u_id,
+                    axn_idx: axn_idx }));
+            }
+        }
+
+        nearest.map(|(_, pick)| pick)
+    }
+
+    /// Returns the world space bounding box, `([min_x, min_y, min_z],
+    /// [max_x, max_y, max_z])`, of all visible slices.
+    fn visible_bounds(&self) -> Option<([f32; 3], [f32; 3])> {
+        self.buffer.visible_slcs().iter().map(|&slc_id| {
+            let (v_size, u_size) = self.buffer.layout().slc_dims(slc_id);
+            let model = self.slc_model(slc_id);
+            let (scl, x_shift, y_shift, z_shift) = (model[0][0], model[3][0], model[3][1],
+                model[3][2]);
+
+            // Hexagon centers span `x = (v + u) * HEX_X`, `y = (u - v) * HEX_Y`
+            // (see `nearest_hex`):
+            let max_x = (v_size + u_size).saturating_sub(2) as f32 * HEX_X;
+            let min_y = -(v_size.saturating_sub(1) as f32) * HEX_Y;
+            let max_y = u_size.saturating_sub(1) as f32 * HEX_Y;
+
+            ([(-HEX_RADIUS) * scl + x_shift, (min_y - HEX_RADIUS) * scl + y_shift, z_shift],
+                [(max_x + HEX_RADIUS) * scl + x_shift, (max_y + HEX_RADIUS) * scl + y_shift,
+                z_shift])
+        }).fold(None, |bounds, (b_min, b_max)| match bounds {
+            Some((min, max)) => Some((
+                [b_min[0].min(min[0]), b_min[1].min(min[1]), b_min[2].min(min[2])],
+                [b_max[0].max(max[0]), b_max[1].max(max[1]), b_max[2].max(max[2])])),
+            None => Some((b_min, b_max)),
+        })
+    }
+
+    /// Positions the camera according to the normalized camera position and
+    /// orbit, relative to a view framing all visible slices.
+    pub fn update_cam_pos(&mut self) {
+        let aspect_ratio = self.surface_dims.1 as f32 / self.surface_dims.0.max(1) as f32;
+        let (min, max) = match self.visible_bounds() {
+            Some(bounds) => bounds,
+            None => return,
+        };
 
-        self.cam_pos_raw = [cam_x_pos, cam_y_pos, cam_z_pos];
+        // Half of the visible height at a distance of 1.0 (see `persp_matrix`):
+        let half_fov_tan = (3.141592 / FOV_ZOOM / 2.0).tan();
+
+        // Distance at which the bounding box just fits in the plane of the
+        // front slice (the horizontal field of view depends on the aspect
+        // ratio), measured from the middle of the stack:
+        let fit_dst = CAM_FIT_MARGIN * (((max[1] - min[1]) / 2.0) / half_fov_tan)
+            .max(((max[0] - min[0]) / 2.0) * aspect_ratio / half_fov_tan)
+            + (max[2] - min[2]) / 2.0;
+        let dst = fit_dst * CAM_ZOOM_BASE.powf(self.cam_pos_norm[2] - CAM_DEFAULT_DST);
+
+        // Panning by a normalized unit moves one (visible) screen width/height:
+        let view_height = 2.0 * dst * half_fov_tan;
+        let view_width = view_height / aspect_ratio;
+
+        // The point looked at (the middle of the stack, see `slc_model`):
+        let target = [
+            self.cam_pos_norm[0].mul_add(-view_width, (min[0] + max[0]) / 2.0),
+            self.cam_pos_norm[1].mul_add(view_height, (min[1] + max[1]) / 2.0),
+            (min[2] + max[2]) / 2.0,
+        ];
+
+        // Orbit around the target (looking straight along +z when unrotated):
+        let (yaw, pitch) = (self.cam_orbit[0], self.cam_orbit[1]);
+        self.cam_dir = [yaw.sin() * pitch.cos(), -pitch.sin(), yaw.cos() * pitch.cos()];
+
+        self.cam_pos_raw = [target[0] - self.cam_dir[0] * dst,
+            target[1] - self.cam_dir[1] * dst, target[2] - self.cam_dir[2] * dst];
         // println!("CAMERA POSITION: norm: {:?}, raw: {:?}", self.cam_pos_norm, self.cam_pos_raw);
     }
 
+    /// Resets panning and zoom so that all visible slices are framed.
+    pub fn fit_to_view(&mut self) {
+        self.cam_pos_norm = [0.0, 0.0, CAM_DEFAULT_DST];
+        self.update_cam_pos();
+    }
+
+    /// Resets orbit, panning, and zoom, looking at the slices head-on.
+    pub fn reset_view(&mut self) {
+        self.cam_orbit = [0.0, 0.0];
+        self.fit_to_view();
+    }
+
+    /// Rotates the camera around the point being looked at (horizontal
+    /// movement orbits, vertical movement tilts).
+    pub fn orbit_camera(&mut self, delta: (i32, i32)) {
+        let yaw = self.cam_orbit[0] + delta.0 as f32 * CAM_ORBIT_RATE;
+        let pitch = self.cam_orbit[1] + delta.1 as f32 * CAM_ORBIT_RATE;
+
+        // Keep yaw within (-pi, pi]:
+        let yaw = if yaw > 3.141592 { yaw - 6.283185 }
+            else if yaw <= -3.141592 { yaw + 6.283185 } else { yaw };
+
+        self.cam_orbit = [yaw, pitch.max(-CAM_PITCH_MAX).min(CAM_PITCH_MAX)];
+        self.update_cam_pos();
+    }
+
+    /// Updates the window dimensions (on resize), re-framing the camera if
+    /// the drawn surface has changed size.
+    pub fn set_window_dims(&mut self, window_dims: (u32, u32)) {
+        self.window_dims = window_dims;
+        let surface_dims = match self.viewport {
+            Some(ref vp) => (vp.width, vp.height),
+            None => window_dims,
+        };
+
+        // Minimized windows have no size:
+        if surface_dims != self.surface_dims && surface_dims.0 > 0 && surface_dims.1 > 0 {
+            self.surface_dims = surface_dims;
+            self.update_cam_pos();
+        }
+    }
+
     // TODO: Add scroll/zoom speed adjustment.
     pub fn move_camera(&mut self, delta: (i32, i32)) {
         let adjustment_factor = 1.0;
@@ -203,12 +479,92 @@ impl<'d> HexGrid<'d> {
         self.cam_pos_raw
     }
 
-    pub fn tract_map(&self) -> &SliceTractMap {
-        &self.buffer.tract_map()
+    /// Returns the orbit rotation (yaw, pitch) in radians.
+    pub fn camera_orbit(&self) -> [f32; 2] {
+        self.cam_orbit
+    }
+
+    pub fn layout(&self) -> &TractLayout {
+        &self.buffer.layout()
+    }
+}
+
+
+/// Returns the (v, u) coordinates of the hexagon whose center is nearest to
+/// the grid space point (x, y) if the point lies within it.
+///
+/// Centers are positioned as in the vertex shader:
+/// `x = (v + u) * HEX_X`, `y = (u - v) * HEX_Y`.
+fn nearest_hex(x: f32, y: f32, grid_dims: (u32, u32)) -> Option<(u32, u32)> {
+    let a = x / HEX_X;
+    let b = y / HEX_Y;
+    let v_approx = (a - b) / 2.0;
+    let u_approx = (a + b) / 2.0;
+
+    let mut nearest: Option<((u32, u32), f32)> = None;
+
+    for &v in [v_approx.floor(), v_approx.ceil()].iter() {
+        for &u in [u_approx.floor(), u_approx.ceil()].iter() {
+            if v < 0.0 || u < 0.0 || v >= grid_dims.0 as f32 || u >= grid_dims.1 as f32 {
+                continue;
+            }
+
+            let dx = x - (v + u) * HEX_X;
+            let dy = y - (u - v) * HEX_Y;
+            let dist_sqr = dx * dx + dy * dy;
+
+            if nearest.map(|(_, d)| dist_sqr < d).unwrap_or(true) {
+                nearest = Some(((v as u32, u as u32), dist_sqr));
+            }
+        }
+    }
+
+    match nearest {
+        Some((coords, dist_sqr)) if dist_sqr <= HEX_RADIUS * HEX_RADIUS => Some(coords),
+        _ => None,
     }
 }
 
 
+/// Returns the world space camera position and a point one unit (of eye
+/// space depth) in front of it along the ray through the normalized device
+/// coordinates `ndc` (x, y).
+///
+/// Unprojecting points on the near and far planes instead loses most of its
+/// precision to the depth range of `persp`.
+fn screen_ray(persp: &[[f32; 4]; 4], view: &[[f32; 4]; 4], ndc: (f32, f32))
+        -> Option<([f32; 3], [f32; 3])>
+{
+    let inv_view = invert_4x4(view)?;
+    Some((unproject(&inv_view, [0.0, 0.0, 0.0]),
+        unproject(&inv_view, [ndc.0 / persp[0][0], ndc.1 / persp[1][1], 1.0])))
+}
+
+/// Intersects the ray from `near` through `far` with the plane of a slice
+/// placed by `model` (see `HexGrid::slc_model`).
+///
+/// Returns the distance along the ray (in multiples of `far - near`) and the
+/// (v, u) coordinates of the hexagon hit, if any.
+fn ray_hex(near: [f32; 3], far: [f32; 3], model: &[[f32; 4]; 4], grid_dims: (u32, u32))
+        -> Option<(f32, (u32, u32))>
+{
+    let (scl, x_shift, y_shift, z_shift) = (model[0][0], model[3][0], model[3][1], model[3][2]);
+
+    // Ray/plane intersection:
+    let dz = far[2] - near[2];
+    if dz.abs() < 1e-9 { return None; }
+    let t = (z_shift - near[2]) / dz;
+    if t < 0.0 { return None; }
+    let world_x = near[0] + t * (far[0] - near[0]);
+    let world_y = near[1] + t * (far[1] - near[1]);
+
+    // Grid (model) space:
+    let x = (world_x - x_shift) / scl;
+    let y = (world_y - y_shift) / scl;
+
+    nearest_hex(x, y, grid_dims).map(|coords| (t, coords))
+}
+
 fn identity_4x4() -> [[f32; 4]; 4] {
     [
         [1.0, 0.0, 0.0, 0.0],
@@ -218,6 +574,50 @@ fn identity_4x4() -> [[f32; 4]; 4] {
     ]
 }
 
+/// Inverts a matrix using Gauss-Jordan elimination.
+///
+/// Works regardless of storage order since the inverse of a transpose is the
+/// transpose of the inverse.
+fn invert_4x4(m: &[[f32; 4]; 4]) -> Option<[[f32; 4]; 4]> {
+    let mut a = *m;
+    let mut inv = identity_4x4();
+
+    for col in 0..4 {
+        let pivot = (col..4).fold(col, |p, r| if a[r][col].abs() > a[p][col].abs() { r } else { p });
+        if a[pivot][col].abs() < 1e-12 { return None; }
+        a.swap(col, pivot);
+        inv.swap(col, pivot);
+
+        let p = a[col][col];
+        for k in 0..4 {
+            a[col][k] /= p;
+            inv[col][k] /= p;
+        }
+
+        for row in 0..4 {
+            if row == col { continue; }
+            let f = a[row][col];
+            for k in 0..4 {
+                a[row][k] -= f * a[col][k];
+                inv[row][k] -= f * inv[col][k];
+            }
+        }
+    }
+
+    Some(inv)
+}
+
+/// Transforms a point back into world space using an inverted (view or
+/// persp * view) matrix.
+fn unproject(inv: &[[f32; 4]; 4], pos: [f32; 3]) -> [f32; 3] {
+    let m = inv;
+    let mut out = [0.0f32; 4];
+    for row in 0..4 {
+        out[row] = m[0][row] * pos[0] + m[1][row] * pos[1] + m[2][row] * pos[2] + m[3][row];
+    }
+    [out[0] / out[3], out[1] / out[3], out[2] / out[3]]
+}
+
 
 // Vertex Shader:
 #[allow(non_upper_case_globals)]
@@ -229,11 +629,13 @@ static vertex_shader_src: &'static str = r#"
     in vec3 normal;
     in float state;
     // in uchar state;
+    in vec2 highlight;
 
     out vec3 v_position;
     out vec3 v_color;
     out vec3 v_normal;
     out float v_state;
+    out vec2 v_highlight;
     // out uchar state;
 
     uniform uint grid_v_size;
@@ -263,6 +665,7 @@ static vertex_shader_src: &'static str = r#"
         v_color = color;
         v_position = gl_Position.xyz / gl_Position.w;
         v_state = state;
+        v_highlight = highlight;
     }
 "#;
 
@@ -279,11 +682,20 @@ static fragment_shader_src: &'static str = r#"
     // Determines red component:
     in float v_state;
     // in uchar v_state;
+    // Highlight kind (0: none, 1: synapse source, 2: source on an active
+    // dendrite, 3: selected cell) and synapse strength:
+    in vec2 v_highlight;
 
     out vec4 color;
 
     uniform vec3 u_light_pos;
-    uniform vec3 u_global_color;
+    // Color map lookup tables, one per row (see `color_map.rs`):
+    uniform sampler2D u_color_maps;
+    uniform float u_color_map;
+    uniform float u_color_map_count;
+    // Value scale (0: linear, 1: log, 2: threshold, 3: signed):
+    uniform int u_scale;
+    uniform float u_threshold;
 
     // const float ambient_strength = 0.1;
     const vec3 ambient_color = vec3(0.9, 0.9, 0.9);
@@ -291,6 +703,10 @@ static fragment_shader_src: &'static str = r#"
     const vec3 specular_color = vec3(0.3, 0.3, 0.3);
     const float specular_coeff = 16.0;
 
+    const vec3 source_color = vec3(0.1, 0.9, 0.2);
+    const vec3 source_active_color = vec3(0.1, 0.8, 1.0);
+    const vec3 cell_color = vec3(1.0, 0.95, 0.2);
+
     // // Pastel orange:
     // const vec3 global_color = vec3(0.9607, 0.4745, 0.0);
     // // Pink model:
@@ -300,8 +716,17 @@ static fragment_shader_src: &'static str = r#"
     //     return max(sign(x - y), 0.0);
     // }
 
-    float gt_zero(float val) {
-        return max(sign(val - 0.0002), 0.0);
+    // Scales a state (0.0 - 255.0) to within [0.0, 1.0] (see `ValueScale::apply`):
+    float scaled(float state) {
+        if (u_scale == 1) {
+            return log(1.0 + state) / log(256.0);
+        } else if (u_scale == 2) {
+            return state >= u_threshold ? 1.0 : 0.0;
+        } else if (u_scale == 3) {
+            return ((state >= 128.0 ? state - 256.0 : state) + 128.0) / 255.0;
+        } else {
+            return state / 255.0;
+        }
     }
 
     void main() {
@@ -312,16 +737,18 @@ static fragment_shader_src: &'static str = r#"
         float specular = pow(max(dot(half_direction, normalize(v_normal)), 0.0),
             specular_coeff);
 
-        float state_norm = v_state / 255.0;
-        float state_compressed = state_norm * 0.5;
-        float state_compressed_boosted = state_compressed + (0.5 * gt_zero(state_norm));
-        // float state_compressed_boosted = gt_zero(state_norm);
-
-        vec3 tile_color = vec3(
-            state_compressed_boosted,
-            u_global_color.g,
-            u_global_color.b - (u_global_color.b * state_compressed_boosted)
-        );
+        vec2 lut_coords = vec2((scaled(v_state) * 255.0 + 0.5) / 256.0,
+            (u_color_map + 0.5) / u_color_map_count);
+        vec3 tile_color = texture(u_color_maps, lut_coords).rgb;
+
+        // Blend in highlights (brighter for stronger synapses):
+        float hl_kind = floor(v_highlight.x + 0.5);
+        if (hl_kind > 2.5) {
+            tile_color = mix(tile_color, cell_color, 0.85);
+        } else if (hl_kind > 0.5) {
+            vec3 hl_color = hl_kind > 1.5 ? source_active_color : source_color;
+            tile_color = mix(tile_color, hl_color * (0.3 + 0.7 * v_highlight.y), 0.75);
+        }
 
         color = vec4((ambient_color * tile_color) + diffuse_ampl
             * diffuse_color + specular * specular_color, 1.0);
@@ -386,7 +813,7 @@ implement_vertex!(Vertex, position, color, normal);
 
 /// Returns a column-major perspective matrix.
 fn persp_matrix(width: u32, height: u32, fov_zoom: f32) -> [[f32; 4]; 4] {
-    let zfar = 1024.0;
+    let zfar = 4096.0;
     let znear = 0.1;
 
     // let (width, height) = target.get_dimensions();
@@ -430,9 +857,9 @@ fn view_matrix(position: &[f32; 3], direction: &[f32; 3], up: &[f32; 3]) -> [[f3
              -position[0] * f[0] - position[1] * f[1] - position[2] * f[2]];
 
     [
-        [s[0], u[0], f[0], 0.0],
-        [s[1], u[1], f[1], 0.0],
-        [s[2], u[2], f[2], 0.0],
+        [s_norm[0], u[0], f[0], 0.0],
+        [s_norm[1], u[1], f[1], 0.0],
+        [s_norm[2], u[2], f[2], 0.0],
         [p[0], p[1], p[2], 1.0],
     ]
 }
@@ -538,3 +965,111 @@ fn view_matrix(position: &[f32; 3], direction: &[f32; 3], up: &[f32; 3]) -> [[f3
 //     }
 
 //
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex_center(v: u32, u: u32) -> (f32, f32) {
+        ((v + u) as f32 * HEX_X, (u as f32 - v as f32) * HEX_Y)
+    }
+
+    /// Multiplies two column-major matrices (`a * b`).
+    fn mul_4x4(a: &[[f32; 4]; 4], b: &[[f32; 4]; 4]) -> [[f32; 4]; 4] {
+        let mut out = [[0.0f32; 4]; 4];
+        for col in 0..4 {
+            for row in 0..4 {
+                out[col][row] = (0..4).map(|k| a[k][row] * b[col][k]).sum();
+            }
+        }
+        out
+    }
+
+    fn assert_near(a: &[[f32; 4]; 4], b: &[[f32; 4]; 4]) {
+        for col in 0..4 {
+            for row in 0..4 {
+                assert!((a[col][row] - b[col][row]).abs() < 1e-4, "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn hex_centers() {
+        let grid_dims = (5, 7);
+        for v in 0..grid_dims.0 {
+            for u in 0..grid_dims.1 {
+                let (x, y) = hex_center(v, u);
+                assert_eq!(nearest_hex(x, y, grid_dims), Some((v, u)));
+                let off = HEX_RADIUS * 0.7;
+                assert_eq!(nearest_hex(x + off, y - off * 0.5, grid_dims), Some((v, u)));
+            }
+        }
+    }
+
+    #[test]
+    fn hex_outside_grid() {
+        let grid_dims = (5, 7);
+        let (x, y) = hex_center(0, 0);
+        assert_eq!(nearest_hex(x - HEX_X, y, grid_dims), None);
+        let (x, y) = hex_center(4, 6);
+        assert_eq!(nearest_hex(x + HEX_X, y + HEX_Y, grid_dims), None);
+        assert_eq!(nearest_hex(0.0, 100.0, grid_dims), None);
+    }
+
+    #[test]
+    fn invert() {
+        assert_eq!(invert_4x4(&identity_4x4()), Some(identity_4x4()));
+
+        let m = view_matrix(&[3.0, -2.0, -50.0], &[0.2, 0.1, 1.0], &[0.0, 1.0, 0.0]);
+        let inv = invert_4x4(&m).unwrap();
+        assert_near(&mul_4x4(&m, &inv), &identity_4x4());
+        assert_near(&mul_4x4(&inv, &m), &identity_4x4());
+
+        let mut singular = identity_4x4();
+        singular[2] = [0.0; 4];
+        assert_eq!(invert_4x4(&singular), None);
+    }
+
+    #[test]
+    fn screen_center_picks_hex_under_camera() {
+        let grid_dims = (8, 10);
+        let (scl, shift) = (2.0, [90.0, 50.0, 26.0]);
+        let model = [
+            [scl, 0.0, 0.0, 0.0],
+            [0.0, scl, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [shift[0], shift[1], shift[2], 1.0],
+        ];
+        let persp = persp_matrix(800, 600, FOV_ZOOM);
+
+        let (x, y) = hex_center(3, 5);
+        let target = [x * scl + shift[0], y * scl + shift[1], shift[2]];
+
+        // Head-on and orbited:
+        for dir in [[0.0, 0.0, 1.0], [0.3, -0.2, 0.9f32]].iter() {
+            let len = (dir[0] * dir[0] + dir[1] * dir[1] + dir[2] * dir[2]).sqrt();
+            let cam_pos = [target[0] - dir[0] / len * 300.0, target[1] - dir[1] / len * 300.0,
+                target[2] - dir[2] / len * 300.0];
+            let view = view_matrix(&cam_pos, dir, &[0.0, 1.0, 0.0]);
+
+            let (near, far) = screen_ray(&persp, &view, (0.0, 0.0)).unwrap();
+            let (t, coords) = ray_hex(near, far, &model, grid_dims).unwrap();
+            assert_eq!(coords, (3, 5));
+            assert!((t - 300.0).abs() < 0.01);
+
+            // The screen's edge is well outside the grid:
+            let (near, far) = screen_ray(&persp, &view, (1.0, 0.0)).unwrap();
+            assert_eq!(ray_hex(near, far, &model, grid_dims), None);
+        }
+    }
+
+    #[test]
+    fn slice_behind_camera() {
+        let model = identity_4x4();
+        let view = view_matrix(&[0.0, 0.0, 10.0], &[0.0, 0.0, 1.0], &[0.0, 1.0, 0.0]);
+        let (near, far) = screen_ray(&persp_matrix(800, 600, FOV_ZOOM), &view, (0.0, 0.0))
+            .unwrap();
+        assert_eq!(ray_hex(near, far, &model, (4, 4)), None);
+    }
+}
@@ -7,9 +7,10 @@ use rand::distributions::{Distribution, Range as RandRange};
 // use glium::backend::glutin::{GlutinFacade};
 use glium::backend::glutin::Display;
 use glium::vertex::{VertexBuffer, VertexBufferSlice};
-use bismit::flywheel::AreaInfo;
-use bismit::map::SliceTractMap;
 use bismit::TractReceiver;
+use window::{TractLayout, ActivityHistory, DisplayMode};
+use window::history::DEFAULT_TRAIL_LEN;
+use window::stats::{self, SliceStats, DEFAULT_STATS_THRESHOLD};
 
 #[derive(Copy, Clone, Debug)]
 // impl Vertex {
@@ -23,6 +24,31 @@ pub struct StateVertex {
 implement_vertex!(StateVertex, state);
 
 
+/// Per-instance highlight: `[kind, strength]`, where kind is one of the
+/// `HIGHLIGHT_*` constants and strength is within [0.0, 1.0].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct HighlightVertex {
+    highlight: [f32; 2],
+}
+implement_vertex!(HighlightVertex, highlight);
+
+const HIGHLIGHT_NONE: f32 = 0.0;
+const HIGHLIGHT_SOURCE: f32 = 1.0;
+const HIGHLIGHT_SOURCE_ACTIVE: f32 = 2.0;
+const HIGHLIGHT_CELL: f32 = 3.0;
+
+
+/// The way an axon is highlighted.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Highlight {
+    /// The selected cell.
+    Cell,
+    /// The source of a synapse of the selected cell. `active` indicates that
+    /// the synapse's dendrite is active.
+    Source { strength: f32, active: bool },
+}
+
+
 /// Handles raw state data from a cortical ganglion and feeds it to a [vertex] buffer for rendering.
 // TODO: Rename these buffers to something more clear.
 #[allow(dead_code)]
@@ -30,33 +56,63 @@ pub struct HexGridBuffer {
     raw_states_vec: Arc<Mutex<Vec<u8>>>,
     raw_states_rx: Option<TractReceiver>,
     raw_states_buf: VertexBuffer<StateVertex>,
+    highlights_buf: VertexBuffer<HighlightVertex>,
+    /// Values derived from the history of states (see `DisplayMode`).
+    derived_states_buf: VertexBuffer<StateVertex>,
+    derived_states_vec: Vec<u8>,
+    history: ActivityHistory,
+    display_mode: DisplayMode,
+    /// Statistics of each slice of the most recent frame (empty unless
+    /// `stats_enabled`).
+    slc_stats: Vec<SliceStats>,
+    stats_enabled: bool,
+    stats_threshold: u8,
+    /// Counts of each state within each slice of the most recent frame (empty
+    /// unless `histograms_enabled`).
+    slc_histograms: Vec<Vec<u32>>,
+    histograms_enabled: bool,
     full_slc_range: Range<usize>,
     default_slc_range: Range<usize>,
-    cur_slc_range: Range<usize>,
-    tract_map: SliceTractMap,
+    /// Visible slices, in ascending order.
+    visible_slcs: Vec<usize>,
+    layout: TractLayout,
 }
 
 impl HexGridBuffer {
-    pub fn new(area_info: AreaInfo, display: &Display)
+    pub fn new(layout: TractLayout, display: &Display)
             -> HexGridBuffer
     {
-        let full_slc_range = area_info.tract_map.slc_id_range();
-        let grid_count = area_info.tract_map.axn_count(full_slc_range.clone());
+        let full_slc_range = layout.slc_id_range();
+        let grid_count = layout.axn_count();
         let raw_states_vec: Vec<u8> = iter::repeat(0u8).cycle().take(grid_count).collect();
         let vec_ref = unsafe { &*(&raw_states_vec as *const Vec<u8>
             as *const _ as *const Vec<StateVertex>) };
         // [NOTE]: `persistent` gives performance improvement:
         // let raw_states_buf = VertexBuffer::dynamic(display, vec_ref).unwrap();
         let raw_states_buf = VertexBuffer::persistent(display, vec_ref).unwrap();
+        let highlights_buf = VertexBuffer::dynamic(display,
+            &vec![HighlightVertex::default(); grid_count]).unwrap();
+        let derived_states_buf = VertexBuffer::dynamic(display,
+            &vec![StateVertex { state: 0 }; grid_count]).unwrap();
 
         HexGridBuffer {
             raw_states_vec: Arc::new(Mutex::new(raw_states_vec)),
             raw_states_rx: None,
             raw_states_buf: raw_states_buf,
+            highlights_buf: highlights_buf,
+            derived_states_buf: derived_states_buf,
+            derived_states_vec: Vec::with_capacity(grid_count),
+            history: ActivityHistory::new(grid_count, DEFAULT_TRAIL_LEN),
+            display_mode: DisplayMode::States,
+            slc_stats: Vec::new(),
+            stats_enabled: false,
+            stats_threshold: DEFAULT_STATS_THRESHOLD,
+            slc_histograms: Vec::new(),
+            histograms_enabled: false,
             full_slc_range: full_slc_range.clone(),
             default_slc_range: full_slc_range.clone(),
-            cur_slc_range: full_slc_range.clone(),
-            tract_map: area_info.tract_map,
+            visible_slcs: full_slc_range.clone().collect(),
+            layout: layout,
         }
     }
 
@@ -68,40 +124,233 @@ impl HexGridBuffer {
 
     /// Refreshes the per-instance data within our vertex buffer.
     ///
-    /// Only refreshes if fresh data is available.
-    pub fn refresh_vertex_buf(&mut self) {
+    /// Only refreshes if fresh data is available. Returns true if it was.
+    pub fn refresh_vertex_buf(&mut self) -> bool {
         // The future returned by `.recv(false)` will always immediately
         // resolve without blocking.
-        if let Some(read_buf) = self.raw_states_rx.as_ref().unwrap()
-                .recv(false).wait().unwrap()
-        {
+        let fresh = match self.raw_states_rx {
+            Some(ref rx) => rx.recv(false).wait().unwrap(),
+            None => return false,
+        };
+
+        if let Some(read_buf) = fresh {
             let read_guard = read_buf.read_u8().wait().unwrap();
-            self.write_to_buf(read_guard.as_slice());
+            self.set_states(read_guard.as_slice());
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Replaces the axon states (as when fresh data is received).
+    pub fn set_states(&mut self, raw_states: &[u8]) {
+        self.write_to_buf(raw_states);
+
+        // Keep a copy around for lookups (picking, recording, etc.), passing
+        // the previous frame along to the history first:
+        {
+            let mut raw_states_vec = self.raw_states_vec.lock().unwrap();
+            self.history.update(raw_states, &raw_states_vec);
+            raw_states_vec.clear();
+            raw_states_vec.extend_from_slice(raw_states);
+        }
+
+        self.refresh_stats(raw_states);
+        self.refresh_derived_states();
+    }
+
+    /// Recomputes whichever of the slice statistics and histograms are
+    /// enabled, emptying the others.
+    fn refresh_stats(&mut self, raw_states: &[u8]) {
+        if self.stats_enabled {
+            stats::slice_stats(&self.layout, raw_states, self.stats_threshold,
+                &mut self.slc_stats);
+        } else {
+            self.slc_stats.clear();
+        }
+
+        if self.histograms_enabled {
+            stats::slice_histograms(&self.layout, raw_states, &mut self.slc_histograms);
+        } else {
+            self.slc_histograms.clear();
+        }
+    }
+
+    /// Recomputes the statistics and histograms from the most recently
+    /// received states (as when one is enabled between frames).
+    fn refresh_stats_from_latest(&mut self) {
+        let raw_states_vec = self.raw_states_vec.clone();
+        let raw_states = raw_states_vec.lock().unwrap();
+        self.refresh_stats(&raw_states);
+    }
+
+    /// Recomputes the displayed values when not displaying raw states.
+    fn refresh_derived_states(&mut self) {
+        if self.display_mode == DisplayMode::States { return; }
+
+        {
+            let raw_states_vec = self.raw_states_vec.lock().unwrap();
+            self.history.values(self.display_mode, &raw_states_vec, &mut self.derived_states_vec);
+        }
+
+        if self.derived_states_vec.len() == self.derived_states_buf.len() {
+            let vec_ref = unsafe { &*(self.derived_states_vec.as_slice() as *const [u8]
+                as *const _ as *const [StateVertex]) };
+            self.derived_states_buf.write(vec_ref);
+        }
+    }
+
+    /// Returns the statistics of each slice of the most recent frame (empty
+    /// unless enabled).
+    pub fn slc_stats(&self) -> &[SliceStats] {
+        &self.slc_stats
+    }
+
+    pub fn stats_enabled(&self) -> bool {
+        self.stats_enabled
+    }
+
+    /// Computes slice statistics for each frame while enabled. They cost a
+    /// pass over every axon, so are best left off while nothing shows them.
+    pub fn set_stats_enabled(&mut self, enabled: bool) {
+        if enabled == self.stats_enabled { return; }
+        self.stats_enabled = enabled;
+        self.refresh_stats_from_latest();
+    }
+
+    /// Returns the counts of each state (256 bins) of a slice within the
+    /// most recent frame (`None` unless enabled).
+    pub fn slc_histogram(&self, slc_id: usize) -> Option<&[u32]> {
+        self.slc_histograms.get(slc_id).map(|h| h.as_slice())
+    }
+
+    /// Computes slice histograms for each frame while enabled (see
+    /// `::set_stats_enabled`).
+    pub fn set_histograms_enabled(&mut self, enabled: bool) {
+        if enabled == self.histograms_enabled { return; }
+        self.histograms_enabled = enabled;
+        self.refresh_stats_from_latest();
+    }
+
+    pub fn stats_threshold(&self) -> u8 {
+        self.stats_threshold
+    }
+
+    /// Sets the state at or above which axons are counted by
+    /// `SliceStats::above` (from the next frame on).
+    pub fn set_stats_threshold(&mut self, threshold: u8) {
+        self.stats_threshold = threshold;
+    }
+
+    pub fn display_mode(&self) -> DisplayMode {
+        self.display_mode
+    }
+
+    pub fn set_display_mode(&mut self, mode: DisplayMode) {
+        self.display_mode = mode;
+        self.refresh_derived_states();
+    }
+
+    pub fn history(&self) -> &ActivityHistory {
+        &self.history
+    }
+
+    pub fn history_mut(&mut self) -> &mut ActivityHistory {
+        &mut self.history
+    }
+
+    /// Clears trails and duty cycles.
+    pub fn reset_history(&mut self) {
+        self.history.reset();
+        self.refresh_derived_states();
+    }
+
+    /// Returns the most recently received state of an axon.
+    pub fn axn_state(&self, axn_idx: usize) -> Option<u8> {
+        self.raw_states_vec.lock().unwrap().get(axn_idx).cloned()
+    }
+
+
+    /// Highlights each listed axon, replacing any previous highlights.
+    ///
+    /// Axons listed more than once keep the strongest highlight.
+    pub fn set_highlights(&mut self, highlights: &[(usize, Highlight)]) {
+        let mut vertices = vec![HighlightVertex::default(); self.highlights_buf.len()];
+
+        for &(axn_idx, highlight) in highlights {
+            let vertex = match vertices.get_mut(axn_idx) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let (kind, strength) = (vertex.highlight[0], vertex.highlight[1]);
+            vertex.highlight = match highlight {
+                _ if kind == HIGHLIGHT_CELL => continue,
+                Highlight::Cell => [HIGHLIGHT_CELL, 1.0],
+                Highlight::Source { strength: s, active } => {
+                    let new_kind = if active || kind == HIGHLIGHT_SOURCE_ACTIVE {
+                        HIGHLIGHT_SOURCE_ACTIVE
+                    } else {
+                        HIGHLIGHT_SOURCE
+                    };
+                    [new_kind, s.max(strength)]
+                },
+            };
         }
+
+        self.highlights_buf.write(&vertices);
+    }
+
+    pub fn clear_highlights(&mut self) {
+        let vertices = vec![HighlightVertex { highlight: [HIGHLIGHT_NONE, 0.0] };
+            self.highlights_buf.len()];
+        self.highlights_buf.write(&vertices);
     }
 
     pub fn set_default_slc_range(&mut self, slc_range: Range<usize>) {
         self.default_slc_range = slc_range;
     }
 
-    pub fn set_tract_map(&mut self, tract_map: SliceTractMap) {
-        self.tract_map = tract_map;
+    pub fn set_layout(&mut self, layout: TractLayout) {
+        self.layout = layout;
     }
 
     // pub fn set_current_slc_range(&mut self ) {}
 
     #[allow(dead_code)]
     pub fn use_default_slc_range(&mut self) {
-        self.cur_slc_range = self.default_slc_range.clone();
+        self.visible_slcs = self.default_slc_range.clone().collect();
     }
 
     #[allow(dead_code)]
     pub fn use_full_slc_range(&mut self) {
-        self.cur_slc_range = self.full_slc_range.clone();
+        self.visible_slcs = self.full_slc_range.clone().collect();
+    }
+
+    /// Shows or hides a range of slices.
+    pub fn set_slcs_visible(&mut self, slc_range: Range<usize>, visible: bool) {
+        let full_slc_range = self.full_slc_range.clone();
+        self.visible_slcs.retain(|slc_id| !(slc_range.start <= *slc_id && *slc_id < slc_range.end));
+
+        if visible {
+            self.visible_slcs.extend(slc_range.filter(|slc_id| {
+                full_slc_range.start <= *slc_id && *slc_id < full_slc_range.end
+            }));
+            self.visible_slcs.sort();
+        }
+    }
+
+    pub fn is_slc_visible(&self, slc_id: usize) -> bool {
+        self.visible_slcs.binary_search(&slc_id).is_ok()
+    }
+
+    /// Returns true if every slice is visible.
+    pub fn all_slcs_visible(&self) -> bool {
+        self.visible_slcs.len() == self.full_slc_range.len()
     }
 
     pub fn aff_out_grid_dims(&self) -> (u32, u32) {
-        self.tract_map.slc_dims(self.default_slc_range.start as u8)
+        self.layout.slc_dims(self.default_slc_range.start)
     }
 
 
@@ -127,18 +376,38 @@ impl HexGridBuffer {
 
     /// Returns a slice of the vertex buffer corresponding to a ganglion slice id.
     pub fn raw_states_buf(&self, slc_id: u8) -> VertexBufferSlice<StateVertex> {
-        let axn_id_range: Range<usize> = self.tract_map.axn_id_range(
+        let axn_id_range: Range<usize> = self.layout.axn_id_range(
             (slc_id as usize)..(slc_id as usize + 1));
         self.raw_states_buf.slice(axn_id_range)
             .expect("HexGridBuffer::raw_states_buf(): Slice id out of range")
     }
 
-    pub fn cur_slc_range(&self) -> Range<usize> {
-        self.cur_slc_range.clone()
+    /// Returns a slice of the buffer of values displayed (raw or derived
+    /// states, depending on the display mode) for a ganglion slice id.
+    pub fn display_states_buf(&self, slc_id: u8) -> VertexBufferSlice<StateVertex> {
+        if self.display_mode == DisplayMode::States { return self.raw_states_buf(slc_id); }
+
+        let axn_id_range: Range<usize> = self.layout.axn_id_range(
+            (slc_id as usize)..(slc_id as usize + 1));
+        self.derived_states_buf.slice(axn_id_range)
+            .expect("HexGridBuffer::display_states_buf(): Slice id out of range")
+    }
+
+    /// Returns a slice of the highlight buffer corresponding to a ganglion slice id.
+    pub fn highlights_buf(&self, slc_id: u8) -> VertexBufferSlice<HighlightVertex> {
+        let axn_id_range: Range<usize> = self.layout.axn_id_range(
+            (slc_id as usize)..(slc_id as usize + 1));
+        self.highlights_buf.slice(axn_id_range)
+            .expect("HexGridBuffer::highlights_buf(): Slice id out of range")
+    }
+
+    /// Returns the visible slices, in ascending order.
+    pub fn visible_slcs(&self) -> &[usize] {
+        &self.visible_slcs
     }
 
-    pub fn tract_map(&self) -> &SliceTractMap {
-        &self.tract_map
+    pub fn layout(&self) -> &TractLayout {
+        &self.layout
     }
 
     pub fn set_tract_buffer(&mut self, rx: TractReceiver) {
@@ -0,0 +1,149 @@
+//! Histograms of the states within each visible slice.
+
+use glium::Surface;
+use window::{Overlay, HexGridBuffer};
+use window::overlay::TEXT_COLOR;
+
+/// Number of bins the 256 possible states are collapsed into by default.
+pub const DEFAULT_BIN_COUNT: usize = 16;
+
+const CHART_SIZE: (f32, f32) = (112.0, 48.0);
+const SPACING: f32 = 8.0;
+const PADDING: f32 = 6.0;
+const LABEL_SIZE: f32 = 12.0;
+const BG_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const BAR_COLOR: [f32; 4] = [0.99, 0.60, 0.10, 0.9];
+const ZERO_BAR_COLOR: [f32; 4] = [0.45, 0.45, 0.55, 0.9];
+/// Fraction of the window width the row of charts may span before wrapping.
+const MAX_WIDTH_FRACTION: f32 = 0.5;
+
+
+/// Collapses 256 state counts into `bin_count` bins.
+pub fn collapse_bins(counts: &[u32], bin_count: usize) -> Vec<u32> {
+    let bin_count = bin_count.max(1).min(counts.len().max(1));
+    let mut bins = vec![0u32; bin_count];
+    for (state, &count) in counts.iter().enumerate() {
+        bins[state * bin_count / counts.len()] += count;
+    }
+    bins
+}
+
+
+/// A row of small histograms, one per visible slice, of the states within
+/// the most recent frame.
+///
+/// Bar heights are logarithmic so that sparse activity remains visible next
+/// to the (usually dominant) inactive bin.
+pub struct HistogramPanel {
+    shown: bool,
+    bin_count: usize,
+}
+
+impl HistogramPanel {
+    pub fn new(bin_count: usize) -> HistogramPanel {
+        HistogramPanel { shown: false, bin_count: bin_count.max(1).min(256) }
+    }
+
+    pub fn toggle_shown(&mut self) {
+        self.shown = !self.shown;
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.shown
+    }
+
+    pub fn bin_count(&self) -> usize {
+        self.bin_count
+    }
+
+    /// Draws the panel centered horizontally with its top at `top`.
+    pub fn draw<S: Surface>(&self, overlay: &Overlay, target: &mut S, top: f32,
+            buffer: &HexGridBuffer)
+    {
+        if !self.shown { return; }
+
+        let slcs = buffer.visible_slcs();
+        if slcs.is_empty() { return; }
+
+        let (width, _) = target.get_dimensions();
+        let cell_size = (CHART_SIZE.0 + SPACING, CHART_SIZE.1 + LABEL_SIZE * 1.5 + SPACING);
+        let cols = (((width as f32 * MAX_WIDTH_FRACTION) / cell_size.0) as usize).max(1)
+            .min(slcs.len());
+        let rows = (slcs.len() + cols - 1) / cols;
+
+        let panel_size = (cell_size.0 * cols as f32 - SPACING + PADDING * 2.0,
+            cell_size.1 * rows as f32 - SPACING + PADDING * 2.0);
+        let left = (width as f32 - panel_size.0) / 2.0;
+        overlay.draw_rect(target, (left, top), panel_size, BG_COLOR);
+
+        // Slices are listed from the front (the last slice) to the back:
+        for (i, &slc_id) in slcs.iter().rev().enumerate() {
+            let pos = (left + PADDING + cell_size.0 * (i % cols) as f32,
+                top + PADDING + cell_size.1 * (i / cols) as f32);
+            let label = format!("{} {}", slc_id, buffer.layout().slc_tags(slc_id).unwrap_or(""));
+            overlay.draw_text(target, &label, (pos.0, pos.1 + LABEL_SIZE), LABEL_SIZE, TEXT_COLOR);
+
+            if let Some(counts) = buffer.slc_histogram(slc_id) {
+                self.draw_chart(overlay, target, (pos.0, pos.1 + LABEL_SIZE * 1.5), counts);
+            }
+        }
+    }
+
+    fn draw_chart<S: Surface>(&self, overlay: &Overlay, target: &mut S, pos: (f32, f32),
+            counts: &[u32])
+    {
+        let bins = collapse_bins(counts, self.bin_count);
+        let max = bins.iter().cloned().max().unwrap_or(0);
+        if max == 0 { return; }
+
+        let log_max = (1.0 + max as f32).ln();
+        let bar_width = CHART_SIZE.0 / bins.len() as f32;
+
+        for (i, &count) in bins.iter().enumerate() {
+            if count == 0 { continue; }
+            let height = (1.0 + count as f32).ln() / log_max * CHART_SIZE.1;
+            // The first bin holds the inactive (zero) states (along with any
+            // others collapsed into it):
+            let color = if i == 0 { ZERO_BAR_COLOR } else { BAR_COLOR };
+            overlay.draw_rect(target, (pos.0 + bar_width * i as f32,
+                pos.1 + CHART_SIZE.1 - height), ((bar_width - 1.0).max(1.0), height), color);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::collapse_bins;
+
+    #[test]
+    fn collapse() {
+        let counts: Vec<u32> = (0..256).map(|s| s as u32).collect();
+        let bins = collapse_bins(&counts, 16);
+        assert_eq!(bins.len(), 16);
+        // Bin 0 holds states 0 - 15, bin 15 states 240 - 255:
+        assert_eq!(bins[0], (0..16).sum::<u32>());
+        assert_eq!(bins[15], (240..256).sum::<u32>());
+        assert_eq!(bins.iter().sum::<u32>(), counts.iter().sum::<u32>());
+    }
+
+    #[test]
+    fn uneven_bins() {
+        let mut counts = vec![0u32; 256];
+        counts[0] = 5;
+        counts[85] = 1;
+        counts[86] = 2;
+        counts[255] = 3;
+        // 256 states into 3 bins: 0 - 85, 86 - 170, 171 - 255.
+        assert_eq!(collapse_bins(&counts, 3), vec![6, 2, 3]);
+    }
+
+    #[test]
+    fn bin_count_limits() {
+        let counts = vec![1u32; 256];
+        assert_eq!(collapse_bins(&counts, 0), vec![256]);
+        assert_eq!(collapse_bins(&counts, 1), vec![256]);
+        assert_eq!(collapse_bins(&counts, 1000), counts);
+        assert_eq!(collapse_bins(&[], 16), vec![0]);
+    }
+}
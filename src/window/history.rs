@@ -0,0 +1,274 @@
+//! Activity history: fading trails, duty cycles, and frame differences.
+
+/// Number of frames over which trails fade out by default.
+pub const DEFAULT_TRAIL_LEN: u32 = 16;
+
+/// Values displayed for each kind of change in `DisplayMode::Difference`
+/// (see `ColorMap::Difference`).
+pub const DIFF_STAYED_OFF: u8 = 0;
+pub const DIFF_TURNED_OFF: u8 = 85;
+pub const DIFF_STAYED_ON: u8 = 170;
+pub const DIFF_TURNED_ON: u8 = 255;
+
+
+/// What is displayed for each axon.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisplayMode {
+    /// The most recently received state.
+    States,
+    /// Recently active axons, fading out over the trail length.
+    Trails,
+    /// The fraction of frames in which each axon has been active.
+    DutyCycle,
+    /// The change since the previous frame: turned on, turned off, stayed
+    /// on, or stayed off.
+    Difference,
+}
+
+impl DisplayMode {
+    /// Returns the mode following this one (wrapping around).
+    pub fn next(&self) -> DisplayMode {
+        match *self {
+            DisplayMode::States => DisplayMode::Trails,
+            DisplayMode::Trails => DisplayMode::DutyCycle,
+            DisplayMode::DutyCycle => DisplayMode::Difference,
+            DisplayMode::Difference => DisplayMode::States,
+        }
+    }
+}
+
+
+/// Per-axon history accumulated from each received frame of states.
+#[derive(Clone, Debug)]
+pub struct ActivityHistory {
+    /// Remaining brightness of each axon's trail (255 when just active).
+    trails: Vec<u8>,
+    trail_len: u32,
+    /// Number of frames in which each axon has been active.
+    active_counts: Vec<u32>,
+    frame_count: u32,
+    /// States of the frame before the most recent one.
+    prev_states: Vec<u8>,
+}
+
+impl ActivityHistory {
+    pub fn new(axn_count: usize, trail_len: u32) -> ActivityHistory {
+        ActivityHistory {
+            trails: vec![0; axn_count],
+            trail_len: trail_len.max(1),
+            active_counts: vec![0; axn_count],
+            frame_count: 0,
+            prev_states: vec![0; axn_count],
+        }
+    }
+
+    /// Adds a frame of states. `prev_states` are the states of the previous
+    /// frame.
+    pub fn update(&mut self, states: &[u8], prev_states: &[u8]) {
+        self.prev_states.clear();
+        self.prev_states.extend_from_slice(prev_states);
+
+        let fade = (255 / self.trail_len).max(1) as u8;
+
+        for ((&state, trail), count) in states.iter().zip(self.trails.iter_mut())
+                .zip(self.active_counts.iter_mut())
+        {
+            if state > 0 {
+                *trail = 255;
+                *count += 1;
+            } else {
+                *trail = trail.saturating_sub(fade);
+            }
+        }
+        self.frame_count += 1;
+    }
+
+    /// Clears trails and duty cycles.
+    pub fn reset(&mut self) {
+        for t in self.trails.iter_mut() { *t = 0; }
+        for c in self.active_counts.iter_mut() { *c = 0; }
+        self.frame_count = 0;
+    }
+
+    pub fn trail_len(&self) -> u32 {
+        self.trail_len
+    }
+
+    pub fn set_trail_len(&mut self, trail_len: u32) {
+        self.trail_len = trail_len.max(1);
+    }
+
+    /// Number of frames added since the last reset.
+    pub fn frame_count(&self) -> u32 {
+        self.frame_count
+    }
+
+    /// Returns the value displayed for each axon in `mode`, written into
+    /// `values` (scaled to 0 - 255).
+    pub fn values(&self, mode: DisplayMode, states: &[u8], values: &mut Vec<u8>) {
+        values.clear();
+
+        match mode {
+            DisplayMode::States => values.extend_from_slice(states),
+            DisplayMode::Trails => values.extend_from_slice(&self.trails),
+            DisplayMode::DutyCycle => {
+                let frames = self.frame_count.max(1) as u64;
+                values.extend(self.active_counts.iter()
+                    .map(|&c| ((c as u64 * 255 + frames / 2) / frames) as u8));
+            },
+            DisplayMode::Difference => {
+                values.extend(states.iter().zip(self.prev_states.iter())
+                    .map(|(&state, &prev)| diff_value(prev > 0, state > 0)));
+            },
+        }
+    }
+
+    /// Describes the change in an axon's state since the previous frame.
+    pub fn change(&self, axn_idx: usize, state: u8) -> Option<&'static str> {
+        self.prev_states.get(axn_idx).map(|&prev| match (prev > 0, state > 0) {
+            (false, true) => "turned on",
+            (true, false) => "turned off",
+            (true, true) => "stayed on",
+            (false, false) => "stayed off",
+        })
+    }
+
+    /// Returns the duty cycle (0.0 - 1.0) of an axon.
+    pub fn duty_cycle(&self, axn_idx: usize) -> Option<f32> {
+        self.active_counts.get(axn_idx)
+            .map(|&c| c as f32 / self.frame_count.max(1) as f32)
+    }
+
+    /// Describes the display mode, for legends and the like.
+    pub fn describe(&self, mode: DisplayMode) -> String {
+        match mode {
+            DisplayMode::States => "States".to_owned(),
+            DisplayMode::Trails => format!("Trails ({} frames)", self.trail_len),
+            DisplayMode::DutyCycle => format!("Duty cycle ({} frames)", self.frame_count),
+            DisplayMode::Difference => "Change since the previous frame".to_owned(),
+        }
+    }
+}
+
+
+fn diff_value(was_active: bool, is_active: bool) -> u8 {
+    match (was_active, is_active) {
+        (false, true) => DIFF_TURNED_ON,
+        (true, false) => DIFF_TURNED_OFF,
+        (true, true) => DIFF_STAYED_ON,
+        (false, false) => DIFF_STAYED_OFF,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(history: &ActivityHistory, mode: DisplayMode, states: &[u8]) -> Vec<u8> {
+        let mut values = Vec::new();
+        history.values(mode, states, &mut values);
+        values
+    }
+
+    #[test]
+    fn trails_fade() {
+        let mut history = ActivityHistory::new(2, 4);
+        history.update(&[1, 0], &[0, 0]);
+        assert_eq!(values(&history, DisplayMode::Trails, &[]), vec![255, 0]);
+
+        // 255 / 4 = 63 per frame, reaching zero after `trail_len` frames:
+        let mut expected = 255u8;
+        for _ in 0..4 {
+            history.update(&[0, 0], &[0, 0]);
+            expected = expected.saturating_sub(63);
+            assert_eq!(values(&history, DisplayMode::Trails, &[]), vec![expected, 0]);
+        }
+        assert_eq!(expected, 3);
+        history.update(&[0, 0], &[0, 0]);
+        assert_eq!(values(&history, DisplayMode::Trails, &[]), vec![0, 0]);
+
+        // Reactivation restores full brightness:
+        history.update(&[7, 0], &[0, 0]);
+        assert_eq!(values(&history, DisplayMode::Trails, &[]), vec![255, 0]);
+    }
+
+    #[test]
+    fn long_trails_still_fade() {
+        let mut history = ActivityHistory::new(1, 1000);
+        history.update(&[1], &[0]);
+        history.update(&[0], &[1]);
+        assert_eq!(values(&history, DisplayMode::Trails, &[]), vec![254]);
+    }
+
+    #[test]
+    fn duty_cycle_rounding() {
+        let mut history = ActivityHistory::new(3, 1);
+        for &states in [[1u8, 1, 0], [1, 0, 0], [1, 0, 0]].iter() {
+            history.update(&states, &[0, 0, 0]);
+        }
+        assert_eq!(history.frame_count(), 3);
+        assert_eq!(values(&history, DisplayMode::DutyCycle, &[]), vec![255, 85, 0]);
+
+        let mut history = ActivityHistory::new(2, 1);
+        for &states in [[1u8, 1], [0, 1], [0, 1], [0, 0], [0, 0], [0, 0], [0, 0]].iter() {
+            history.update(&states, &[0, 0]);
+        }
+        // 255 / 7 = 36.4 and 3 * 255 / 7 = 109.3 round down:
+        assert_eq!(values(&history, DisplayMode::DutyCycle, &[]), vec![36, 109]);
+
+        // 255 / 2 = 127.5 rounds up:
+        let mut history = ActivityHistory::new(1, 1);
+        history.update(&[1], &[0]);
+        history.update(&[0], &[1]);
+        assert_eq!(values(&history, DisplayMode::DutyCycle, &[]), vec![128]);
+        assert_eq!(history.duty_cycle(0), Some(0.5));
+        assert_eq!(history.duty_cycle(1), None);
+    }
+
+    #[test]
+    fn duty_cycle_without_frames() {
+        let history = ActivityHistory::new(2, 1);
+        assert_eq!(values(&history, DisplayMode::DutyCycle, &[]), vec![0, 0]);
+        assert_eq!(history.duty_cycle(0), Some(0.0));
+    }
+
+    #[test]
+    fn reset() {
+        let mut history = ActivityHistory::new(2, 8);
+        history.update(&[1, 0], &[0, 0]);
+        history.update(&[1, 1], &[1, 0]);
+        history.reset();
+
+        assert_eq!(history.frame_count(), 0);
+        assert_eq!(values(&history, DisplayMode::Trails, &[]), vec![0, 0]);
+        assert_eq!(values(&history, DisplayMode::DutyCycle, &[]), vec![0, 0]);
+        assert_eq!(history.trail_len(), 8);
+
+        history.update(&[0, 1], &[1, 1]);
+        assert_eq!(values(&history, DisplayMode::DutyCycle, &[]), vec![0, 255]);
+    }
+
+    #[test]
+    fn differences() {
+        assert_eq!(diff_value(false, false), DIFF_STAYED_OFF);
+        assert_eq!(diff_value(true, false), DIFF_TURNED_OFF);
+        assert_eq!(diff_value(true, true), DIFF_STAYED_ON);
+        assert_eq!(diff_value(false, true), DIFF_TURNED_ON);
+
+        let mut history = ActivityHistory::new(4, 1);
+        let (prev, states) = ([0u8, 9, 9, 0], [0u8, 0, 3, 1]);
+        history.update(&states, &prev);
+        assert_eq!(values(&history, DisplayMode::Difference, &states),
+            vec![DIFF_STAYED_OFF, DIFF_TURNED_OFF, DIFF_STAYED_ON, DIFF_TURNED_ON]);
+        assert_eq!(history.change(1, states[1]), Some("turned off"));
+        assert_eq!(history.change(3, states[3]), Some("turned on"));
+        assert_eq!(history.change(4, 0), None);
+    }
+
+    #[test]
+    fn states_pass_through() {
+        let history = ActivityHistory::new(3, 1);
+        assert_eq!(values(&history, DisplayMode::States, &[0, 5, 255]), vec![0, 5, 255]);
+    }
+}
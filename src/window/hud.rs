@@ -0,0 +1,321 @@
+//! A heads-up display assembled from registered info providers.
+
+use glium::Surface;
+use window::{Window, Overlay, SliceStats};
+use window::overlay::TEXT_COLOR;
+
+/// Font size in pixels before scaling.
+const TEXT_SIZE: f32 = 15.0;
+const LINE_SPACING: f32 = 1.4;
+const PADDING: f32 = 6.0;
+const INDENT: f32 = 10.0;
+const BG_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const TITLE_COLOR: (f32, f32, f32, f32) = (0.65, 0.80, 0.99, 1.0);
+/// Window height at which the HUD is drawn at its nominal size.
+const REFERENCE_HEIGHT: f32 = 900.0;
+/// Limits of the scaling applied for the window height.
+const WINDOW_SCALE_MIN: f32 = 0.75;
+const WINDOW_SCALE_MAX: f32 = 1.5;
+
+
+/// Provides the lines of a HUD section.
+pub trait HudProvider {
+    /// A short lowercase name identifying the section (see `Hud::show_only`).
+    fn name(&self) -> &'static str;
+
+    fn title(&self) -> &'static str;
+
+    /// Returns the lines to display. The section is omitted when empty.
+    fn lines(&self, window: &Window, window_dims: (u32, u32)) -> Vec<String>;
+}
+
+
+/// Camera position, distance, and orbit of the main view.
+pub struct CameraHud;
+
+impl HudProvider for CameraHud {
+    fn name(&self) -> &'static str { "camera" }
+    fn title(&self) -> &'static str { "Camera" }
+
+    fn lines(&self, window: &Window, window_dims: (u32, u32)) -> Vec<String> {
+        let pos = window.hex_grid.cam_pos_raw();
+        let orbit = window.hex_grid.camera_orbit();
+        vec![
+            format!("Position: ({:.2}, {:.2}, {:.2})", pos[0], pos[1], pos[2]),
+            format!("View Distance: {:.0}%", window.hex_grid.camera_pos()[2] * 100.0),
+            format!("Orbit: {:.0} deg, Tilt: {:.0} deg", orbit[0].to_degrees(),
+                orbit[1].to_degrees()),
+            format!("Window: {} X {}", window_dims.0, window_dims.1),
+        ]
+    }
+}
+
+
+/// Frame rate and cycle counts (or replay position).
+pub struct TimingHud;
+
+impl HudProvider for TimingHud {
+    fn name(&self) -> &'static str { "timing" }
+    fn title(&self) -> &'static str { "Timing" }
+
+    fn lines(&self, window: &Window, _: (u32, u32)) -> Vec<String> {
+        let mut lines = vec![format!("FPS: {:.1}", window.stats.fps())];
+
+        match window.replay {
+            Some(ref replay) => {
+                lines.push(format!("Frame: {} / {}", replay.frame_idx() + 1, replay.frame_count()));
+                lines.push(format!("Cycle: {}", replay.cycle()));
+            },
+            None => {
+                let status = &window.cycle_status;
+                lines.push(format!("Current Cycle: {}", status.cur_cycle()));
+                lines.push(format!("Current CPS: {:.1}", status.cur_cps()));
+                lines.push(format!("Total Cycles: {}", status.ttl_cycles()));
+                lines.push(format!("Total CPS: {:.1}", status.ttl_cps()));
+                lines.push(format!("Iters per Run: {}", window.iters_pending));
+                lines.push(format!("Speed Limit: {}", window.throttle.limit().describe()));
+                if let Some(remaining) = window.paused_iters {
+                    lines.push(format!("Paused: {} iterations remaining", remaining));
+                }
+            },
+        }
+        lines
+    }
+}
+
+
+/// Name and size of the displayed area(s).
+pub struct AreaHud;
+
+impl HudProvider for AreaHud {
+    fn name(&self) -> &'static str { "area" }
+    fn title(&self) -> &'static str { "Area" }
+
+    fn lines(&self, window: &Window, _: (u32, u32)) -> Vec<String> {
+        let mut lines = vec![
+            format!("Name: \"{}\"", window.area_name),
+            format!("Size: {} X {}", window.grid_dims.0, window.grid_dims.1),
+            format!("Axons: {}", window.hex_grid.layout().axn_count()),
+        ];
+        if !window.extra_views.is_empty() {
+            let names: Vec<_> = window.extra_views.iter().map(|v| v.name.as_str()).collect();
+            lines.push(format!("Also Showing: {}", names.join(", ")));
+        }
+        lines
+    }
+}
+
+
+/// Visible layers and the display mode.
+pub struct LayersHud;
+
+impl HudProvider for LayersHud {
+    fn name(&self) -> &'static str { "layers" }
+    fn title(&self) -> &'static str { "Layers" }
+
+    fn lines(&self, window: &Window, _: (u32, u32)) -> Vec<String> {
+        let buffer = &window.hex_grid.buffer;
+        let layers = buffer.layout().layers();
+        let visible: Vec<_> = layers.iter().rev()
+            .filter(|&&(_, ref slc_range)| {
+                slc_range.clone().any(|slc_id| buffer.is_slc_visible(slc_id))
+            })
+            .map(|&(tags, _)| tags)
+            .collect();
+
+        vec![
+            format!("Visible: {} of {} ({} slices)", visible.len(), layers.len(),
+                buffer.visible_slcs().len()),
+            format!("Showing: {}", visible.join(", ")),
+            format!("Display: {}", buffer.history().describe(buffer.display_mode())),
+        ]
+    }
+}
+
+
+/// Activity statistics of all visible slices combined.
+pub struct StatsHud;
+
+impl HudProvider for StatsHud {
+    fn name(&self) -> &'static str { "stats" }
+    fn title(&self) -> &'static str { "Activity" }
+
+    fn lines(&self, window: &Window, _: (u32, u32)) -> Vec<String> {
+        let buffer = &window.hex_grid.buffer;
+        let stats = buffer.visible_slcs().iter()
+            .filter_map(|&slc_id| buffer.slc_stats().get(slc_id))
+            .fold(SliceStats::default(), |acc, s| acc.merge(s));
+
+        vec![
+            format!("Active: {:.2}% ({} of {})", stats.active_fraction() * 100.0, stats.active,
+                stats.count),
+            format!("Mean State: {:.1}, Max: {}", stats.mean(), stats.max),
+            format!("At or Above {}: {}", buffer.stats_threshold(), stats.above),
+        ]
+    }
+}
+
+
+/// The cell under the mouse or, failing that, the selected cell.
+pub struct PickedCellHud;
+
+impl HudProvider for PickedCellHud {
+    fn name(&self) -> &'static str { "cell" }
+    fn title(&self) -> &'static str { "Cell" }
+
+    fn lines(&self, window: &Window, _: (u32, u32)) -> Vec<String> {
+        let hovered = match window.has_mouse_focus {
+            true => window.hex_grid.pick(window.mouse_pos),
+            false => None,
+        };
+        let pick = match hovered.or_else(|| window.selection.as_ref().map(|s| s.pick())) {
+            Some(pick) => pick,
+            None => return Vec::new(),
+        };
+
+        let buffer = &window.hex_grid.buffer;
+        let state = buffer.axn_state(pick.axn_idx)
+            .map(|s| s.to_string()).unwrap_or_else(|| "-".to_owned());
+        vec![
+            format!("Slice: {} [v: {}, u: {}]", pick.slc_id, pick.v_id, pick.u_id),
+            format!("Layer: {}", buffer.layout().slc_tags(pick.slc_id).unwrap_or("-")),
+            format!("Axon: {}, State: {}", pick.axn_idx, state),
+        ]
+    }
+}
+
+
+struct Section {
+    provider: Box<HudProvider>,
+    shown: bool,
+}
+
+
+/// A column of sections, one per registered provider, each of which can be
+/// toggled individually.
+///
+/// Text is scaled by the display's DPI factor and by the window height
+/// (within limits), then by a user-adjustable factor.
+pub struct Hud {
+    shown: bool,
+    sections: Vec<Section>,
+    scale: f32,
+    dpi_factor: f32,
+}
+
+impl Hud {
+    /// Returns an empty HUD. See `::with_defaults`.
+    pub fn new() -> Hud {
+        Hud { shown: true, sections: Vec::new(), scale: 1.0, dpi_factor: 1.0 }
+    }
+
+    /// Returns a HUD with each built-in provider registered.
+    pub fn with_defaults() -> Hud {
+        Hud::new()
+            .provider(Box::new(CameraHud))
+            .provider(Box::new(TimingHud))
+            .provider(Box::new(AreaHud))
+            .provider(Box::new(LayersHud))
+            .provider(Box::new(StatsHud))
+            .provider(Box::new(PickedCellHud))
+    }
+
+    /// Registers a provider, drawn below those registered before it.
+    pub fn provider(mut self, provider: Box<HudProvider>) -> Hud {
+        self.sections.push(Section { provider: provider, shown: true });
+        self
+    }
+
+    /// Returns the names of each section in order.
+    pub fn section_names(&self) -> Vec<&'static str> {
+        self.sections.iter().map(|s| s.provider.name()).collect()
+    }
+
+    /// Shows only the named sections. Returns the first unknown name, if any.
+    pub fn show_only<S: AsRef<str>>(&mut self, names: &[S]) -> Result<(), String> {
+        let known = self.section_names();
+        if let Some(name) = names.iter().find(|n| !known.iter().any(|&k| k == n.as_ref())) {
+            return Err(name.as_ref().to_owned());
+        }
+        for section in self.sections.iter_mut() {
+            section.shown = names.iter().any(|n| n.as_ref() == section.provider.name());
+        }
+        Ok(())
+    }
+
+    /// Shows or hides the whole HUD.
+    pub fn toggle_shown(&mut self) {
+        self.shown = !self.shown;
+    }
+
+    /// Returns true if the HUD and the named section are both shown.
+    pub fn is_section_shown(&self, name: &str) -> bool {
+        self.shown && self.sections.iter().any(|s| s.shown && s.provider.name() == name)
+    }
+
+    /// Shows or hides a section by index (registration order).
+    pub fn toggle_section(&mut self, idx: usize) {
+        if let Some(section) = self.sections.get_mut(idx) {
+            section.shown = !section.shown;
+        }
+    }
+
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale.max(0.25).min(4.0);
+    }
+
+    pub fn set_dpi_factor(&mut self, dpi_factor: f32) {
+        if dpi_factor > 0.0 { self.dpi_factor = dpi_factor; }
+    }
+
+    /// Returns the total scale applied within a window of `window_dims`.
+    fn effective_scale(&self, window_dims: (u32, u32)) -> f32 {
+        let window_scale = (window_dims.1 as f32 / (REFERENCE_HEIGHT * self.dpi_factor))
+            .max(WINDOW_SCALE_MIN).min(WINDOW_SCALE_MAX);
+        self.dpi_factor * window_scale * self.scale
+    }
+
+    /// Draws the HUD with its top left corner at `pos`, returning its size
+    /// (zero if hidden or empty).
+    pub fn draw<S: Surface>(&self, overlay: &Overlay, target: &mut S, pos: (f32, f32),
+            window: &Window) -> (f32, f32)
+    {
+        if !self.shown { return (0.0, 0.0); }
+
+        let window_dims = target.get_dimensions();
+        let blocks: Vec<_> = self.sections.iter()
+            .filter(|s| s.shown)
+            .map(|s| (s.provider.title(), s.provider.lines(window, window_dims)))
+            .filter(|&(_, ref lines)| !lines.is_empty())
+            .collect();
+        if blocks.is_empty() { return (0.0, 0.0); }
+
+        let scale = self.effective_scale(window_dims);
+        let (text_size, padding, indent) = (TEXT_SIZE * scale, PADDING * scale, INDENT * scale);
+        let line_height = text_size * LINE_SPACING;
+
+        let line_count = blocks.iter().map(|&(_, ref lines)| lines.len() + 1).sum::<usize>();
+        let width = blocks.iter().flat_map(|&(title, ref lines)| {
+            Some(overlay.text_width(title, text_size)).into_iter()
+                .chain(lines.iter().map(|l| overlay.text_width(l, text_size) + indent))
+        }).fold(0.0f32, |a, b| a.max(b));
+        let size = (width + padding * 2.0, line_height * line_count as f32
+            + padding * (blocks.len() as f32 + 1.0));
+
+        overlay.draw_rect(target, pos, size, BG_COLOR);
+
+        let mut y = pos.1 + padding;
+        for (title, lines) in blocks {
+            y += line_height;
+            overlay.draw_text(target, title, (pos.0 + padding, y - line_height * 0.3), text_size,
+                TITLE_COLOR);
+            for line in lines.iter() {
+                y += line_height;
+                overlay.draw_text(target, line, (pos.0 + padding + indent,
+                    y - line_height * 0.3), text_size, TEXT_COLOR);
+            }
+            y += padding;
+        }
+        size
+    }
+}
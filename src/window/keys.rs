@@ -0,0 +1,492 @@
+//! Keyboard shortcuts.
+//!
+//! Every shortcut performs an `Action`. Default bindings can be replaced by
+//! name, e.g. from the `[keys]` table of a config file:
+//!
+//! ```toml
+//! [keys]
+//! run_stop = "Return"
+//! screenshot = "F12, Ctrl+S"
+//! ```
+//!
+
+use glium::glutin::{VirtualKeyCode as Vk, ModifiersState};
+
+/// Number of layers (from the top of the layer checklist) toggled with the
+/// number keys by default (`1`-`9`, then `0`). Layers further down can be
+/// bound by name (e.g. `toggle_layer_12`).
+pub const LAYER_KEY_COUNT: usize = 10;
+
+/// Number of HUD sections which can be toggled individually.
+pub const HUD_SECTION_KEY_COUNT: usize = 6;
+
+
+/// Something done in response to a key press.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Starts a run of the pending number of iterations, or stops the
+    /// current one (plays or pauses replays).
+    RunStop,
+    /// Pauses the current run or resumes a paused one (plays or pauses
+    /// replays).
+    PauseResume,
+    Step,
+    MoreIters,
+    FewerIters,
+    /// Shows or hides a layer by its position in the layer checklist.
+    ToggleLayer(usize),
+    ViewOutput,
+    ViewAll,
+    FitView,
+    ResetView,
+    /// Camera navigation (applied to every displayed area).
+    PanLeft,
+    PanRight,
+    ZoomIn,
+    ZoomOut,
+    OrbitLeft,
+    OrbitRight,
+    TiltUp,
+    TiltDown,
+    Screenshot,
+    ToggleRecording,
+    ToggleTractRecording,
+    ToggleLayerList,
+    ToggleStats,
+    TogglePlots,
+    ToggleHistograms,
+    ToggleHud,
+    ToggleHudSection(usize),
+    ToggleFullscreen,
+    NextDisplayMode,
+    ResetHistory,
+    NextColorMap,
+    NextScale,
+    ReplayBack,
+    ReplayForward,
+    ReplayPageBack,
+    ReplayPageForward,
+    ReplayStart,
+    ReplayEnd,
+    ReplaySlower,
+    ReplayFaster,
+    ToggleHelp,
+    Exit,
+}
+
+impl Action {
+    /// Returns every action, in the order listed by the help overlay.
+    pub fn all() -> Vec<Action> {
+        let mut actions = vec![Action::RunStop, Action::PauseResume, Action::Step,
+            Action::MoreIters, Action::FewerIters];
+        actions.extend((0..LAYER_KEY_COUNT).map(Action::ToggleLayer));
+        actions.extend_from_slice(&[Action::ViewOutput, Action::ViewAll, Action::FitView,
+            Action::ResetView, Action::ZoomIn, Action::ZoomOut, Action::PanLeft,
+            Action::PanRight, Action::OrbitLeft, Action::OrbitRight, Action::TiltUp,
+            Action::TiltDown, Action::Screenshot, Action::ToggleRecording,
+            Action::ToggleTractRecording, Action::ToggleLayerList, Action::ToggleStats,
+            Action::TogglePlots, Action::ToggleHistograms, Action::ToggleHud]);
+        actions.extend((0..HUD_SECTION_KEY_COUNT).map(Action::ToggleHudSection));
+        actions.extend_from_slice(&[Action::ToggleFullscreen, Action::NextDisplayMode,
+            Action::ResetHistory, Action::NextColorMap, Action::NextScale, Action::ReplayBack,
+            Action::ReplayForward, Action::ReplayPageBack, Action::ReplayPageForward,
+            Action::ReplayStart, Action::ReplayEnd, Action::ReplaySlower, Action::ReplayFaster,
+            Action::ToggleHelp, Action::Exit]);
+        actions
+    }
+
+    /// Returns the name used to rebind the action.
+    pub fn name(&self) -> String {
+        let name = match *self {
+            Action::RunStop => "run_stop",
+            Action::PauseResume => "pause_resume",
+            Action::Step => "step",
+            Action::MoreIters => "more_iters",
+            Action::FewerIters => "fewer_iters",
+            Action::ToggleLayer(idx) => return format!("toggle_layer_{}", idx + 1),
+            Action::ViewOutput => "view_output",
+            Action::ViewAll => "view_all",
+            Action::FitView => "fit_view",
+            Action::ResetView => "reset_view",
+            Action::PanLeft => "pan_left",
+            Action::PanRight => "pan_right",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::OrbitLeft => "orbit_left",
+            Action::OrbitRight => "orbit_right",
+            Action::TiltUp => "tilt_up",
+            Action::TiltDown => "tilt_down",
+            Action::Screenshot => "screenshot",
+            Action::ToggleRecording => "toggle_recording",
+            Action::ToggleTractRecording => "toggle_tract_recording",
+            Action::ToggleLayerList => "toggle_layer_list",
+            Action::ToggleStats => "toggle_stats",
+            Action::TogglePlots => "toggle_plots",
+            Action::ToggleHistograms => "toggle_histograms",
+            Action::ToggleHud => "toggle_hud",
+            Action::ToggleHudSection(idx) => return format!("toggle_hud_section_{}", idx + 1),
+            Action::ToggleFullscreen => "toggle_fullscreen",
+            Action::NextDisplayMode => "next_display_mode",
+            Action::ResetHistory => "reset_history",
+            Action::NextColorMap => "next_color_map",
+            Action::NextScale => "next_scale",
+            Action::ReplayBack => "replay_back",
+            Action::ReplayForward => "replay_forward",
+            Action::ReplayPageBack => "replay_page_back",
+            Action::ReplayPageForward => "replay_page_forward",
+            Action::ReplayStart => "replay_start",
+            Action::ReplayEnd => "replay_end",
+            Action::ReplaySlower => "replay_slower",
+            Action::ReplayFaster => "replay_faster",
+            Action::ToggleHelp => "toggle_help",
+            Action::Exit => "exit",
+        };
+        name.to_owned()
+    }
+
+    /// Returns the action named `name` (see `::name`).
+    pub fn from_name(name: &str) -> Option<Action> {
+        // Layers beyond `LAYER_KEY_COUNT` are not listed by `::all`:
+        if name.starts_with("toggle_layer_") {
+            if let Ok(n) = name["toggle_layer_".len()..].parse::<usize>() {
+                if n > 0 && Action::ToggleLayer(n - 1).name() == name {
+                    return Some(Action::ToggleLayer(n - 1));
+                }
+            }
+        }
+        Action::all().into_iter().find(|a| a.name() == name)
+    }
+
+    pub fn description(&self) -> String {
+        let desc = match *self {
+            Action::RunStop => "Run / stop (play / pause replays)",
+            Action::PauseResume => "Pause / resume the current run",
+            Action::Step => "Run a single cycle (of the paused run, if any)",
+            Action::MoreIters => "Increase iterations per run (x10)",
+            Action::FewerIters => "Decrease iterations per run (/10)",
+            Action::ToggleLayer(idx) => return format!("Show / hide layer {} (from the top)",
+                idx + 1),
+            Action::ViewOutput => "View output layers",
+            Action::ViewAll => "View all layers",
+            Action::FitView => "Fit the visible slices to the view",
+            Action::ResetView => "Reset the view (undo orbiting)",
+            Action::PanLeft => "Move the camera left",
+            Action::PanRight => "Move the camera right",
+            Action::ZoomIn => "Move the camera closer",
+            Action::ZoomOut => "Move the camera further away",
+            Action::OrbitLeft => "Orbit left around the slices",
+            Action::OrbitRight => "Orbit right around the slices",
+            Action::TiltUp => "Tilt the slices up",
+            Action::TiltDown => "Tilt the slices down",
+            Action::Screenshot => "Save a screenshot",
+            Action::ToggleRecording => "Start / stop recording frames",
+            Action::ToggleTractRecording => "Start / stop recording the tract stream",
+            Action::ToggleLayerList => "Show / hide the layer checklist",
+            Action::ToggleStats => "Show / hide layer statistics",
+            Action::TogglePlots => "Show / hide plots",
+            Action::ToggleHistograms => "Show / hide state histograms",
+            Action::ToggleHud => "Show / hide the HUD",
+            Action::ToggleHudSection(idx) => return format!("Show / hide HUD section {}",
+                idx + 1),
+            Action::ToggleFullscreen => "Toggle fullscreen",
+            Action::NextDisplayMode => "Next display mode",
+            Action::ResetHistory => "Reset trails and duty cycles",
+            Action::NextColorMap => "Next color map (layer under the mouse or all)",
+            Action::NextScale => "Next value scale (layer under the mouse or all)",
+            Action::ReplayBack => "Replay: previous frame",
+            Action::ReplayForward => "Replay: next frame",
+            Action::ReplayPageBack => "Replay: skip back",
+            Action::ReplayPageForward => "Replay: skip forward",
+            Action::ReplayStart => "Replay: first frame",
+            Action::ReplayEnd => "Replay: last frame",
+            Action::ReplaySlower => "Replay: half speed",
+            Action::ReplayFaster => "Replay: double speed",
+            Action::ToggleHelp => "Show / hide this help",
+            Action::Exit => "Exit",
+        };
+        desc.to_owned()
+    }
+}
+
+
+/// A key along with the modifiers which must be held.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyCombo {
+    pub code: Vk,
+    pub shift: bool,
+    pub ctrl: bool,
+}
+
+impl KeyCombo {
+    pub fn new(code: Vk) -> KeyCombo {
+        KeyCombo { code: code, shift: false, ctrl: false }
+    }
+
+    pub fn shift(code: Vk) -> KeyCombo {
+        KeyCombo { code: code, shift: true, ctrl: false }
+    }
+
+    pub fn ctrl(code: Vk) -> KeyCombo {
+        KeyCombo { code: code, shift: false, ctrl: true }
+    }
+
+    /// Parses a key name, optionally prefixed by modifiers, e.g. "Space",
+    /// "Shift+Slash" or "Ctrl+Q" (see `KEY_NAMES`).
+    pub fn parse(text: &str) -> Option<KeyCombo> {
+        let mut parts: Vec<_> = text.split('+').map(|p| p.trim()).collect();
+        let key = match parts.pop() {
+            Some(key) => key,
+            None => return None,
+        };
+
+        let mut combo = match KEY_NAMES.iter().find(|&&(name, _, label)| {
+            name.eq_ignore_ascii_case(key) || label.eq_ignore_ascii_case(key)
+        }) {
+            Some(&(_, code, _)) => KeyCombo::new(code),
+            None => return None,
+        };
+
+        for modifier in parts {
+            match modifier.to_lowercase().as_str() {
+                "shift" => combo.shift = true,
+                "ctrl" | "control" => combo.ctrl = true,
+                _ => return None,
+            }
+        }
+        Some(combo)
+    }
+
+    /// Returns a label for the help overlay.
+    pub fn label(&self) -> String {
+        // Shifted symbols (US layout):
+        match (self.code, self.shift, self.ctrl) {
+            (Vk::Slash, true, false) => return "?".to_owned(),
+            (Vk::Equals, true, false) => return "+".to_owned(),
+            _ => (),
+        }
+
+        let key = KEY_NAMES.iter().find(|&&(_, code, _)| code == self.code)
+            .map(|&(_, _, label)| label.to_owned())
+            .unwrap_or_else(|| format!("{:?}", self.code));
+        format!("{}{}{}", if self.ctrl { "Ctrl+" } else { "" },
+            if self.shift { "Shift+" } else { "" }, key)
+    }
+
+    /// Returns true if this combo is satisfied by `code` pressed while
+    /// holding `modifiers`.
+    fn matches(&self, code: Vk, modifiers: ModifiersState) -> bool {
+        self.code == code && self.ctrl == modifiers.ctrl && (!self.shift || modifiers.shift)
+    }
+}
+
+
+/// Key names accepted when rebinding, with the labels shown in the help
+/// overlay (which are also accepted).
+const KEY_NAMES: &'static [(&'static str, Vk, &'static str)] = &[
+    ("A", Vk::A, "A"), ("B", Vk::B, "B"), ("C", Vk::C, "C"), ("D", Vk::D, "D"),
+    ("E", Vk::E, "E"), ("F", Vk::F, "F"), ("G", Vk::G, "G"), ("H", Vk::H, "H"),
+    ("I", Vk::I, "I"), ("J", Vk::J, "J"), ("K", Vk::K, "K"), ("L", Vk::L, "L"),
+    ("M", Vk::M, "M"), ("N", Vk::N, "N"), ("O", Vk::O, "O"), ("P", Vk::P, "P"),
+    ("Q", Vk::Q, "Q"), ("R", Vk::R, "R"), ("S", Vk::S, "S"), ("T", Vk::T, "T"),
+    ("U", Vk::U, "U"), ("V", Vk::V, "V"), ("W", Vk::W, "W"), ("X", Vk::X, "X"),
+    ("Y", Vk::Y, "Y"), ("Z", Vk::Z, "Z"),
+    ("Key1", Vk::Key1, "1"), ("Key2", Vk::Key2, "2"), ("Key3", Vk::Key3, "3"),
+    ("Key4", Vk::Key4, "4"), ("Key5", Vk::Key5, "5"), ("Key6", Vk::Key6, "6"),
+    ("Key7", Vk::Key7, "7"), ("Key8", Vk::Key8, "8"), ("Key9", Vk::Key9, "9"),
+    ("Key0", Vk::Key0, "0"),
+    ("F1", Vk::F1, "F1"), ("F2", Vk::F2, "F2"), ("F3", Vk::F3, "F3"), ("F4", Vk::F4, "F4"),
+    ("F5", Vk::F5, "F5"), ("F6", Vk::F6, "F6"), ("F7", Vk::F7, "F7"), ("F8", Vk::F8, "F8"),
+    ("F9", Vk::F9, "F9"), ("F10", Vk::F10, "F10"), ("F11", Vk::F11, "F11"),
+    ("F12", Vk::F12, "F12"),
+    ("Space", Vk::Space, "Space"), ("Return", Vk::Return, "Enter"),
+    ("Escape", Vk::Escape, "Esc"), ("Back", Vk::Back, "Backspace"), ("Tab", Vk::Tab, "Tab"),
+    ("Delete", Vk::Delete, "Del"), ("Insert", Vk::Insert, "Ins"),
+    ("Pause", Vk::Pause, "Pause"), ("Home", Vk::Home, "Home"), ("End", Vk::End, "End"),
+    ("PageUp", Vk::PageUp, "Page Up"), ("PageDown", Vk::PageDown, "Page Down"),
+    ("Left", Vk::Left, "Left"), ("Right", Vk::Right, "Right"),
+    ("Up", Vk::Up, "Up"), ("Down", Vk::Down, "Down"),
+    ("Period", Vk::Period, "."), ("Comma", Vk::Comma, ","), ("Slash", Vk::Slash, "/"),
+    ("Backslash", Vk::Backslash, "\\"), ("Semicolon", Vk::Semicolon, ";"),
+    ("Apostrophe", Vk::Apostrophe, "'"), ("Grave", Vk::Grave, "`"),
+    ("Minus", Vk::Minus, "-"), ("Equals", Vk::Equals, "="),
+    ("LBracket", Vk::LBracket, "["), ("RBracket", Vk::RBracket, "]"),
+    ("Add", Vk::Add, "Num +"), ("Subtract", Vk::Subtract, "Num -"),
+];
+
+
+/// The keys bound to each action.
+#[derive(Clone, Debug)]
+pub struct KeyBindings {
+    bindings: Vec<(KeyCombo, Action)>,
+}
+
+impl KeyBindings {
+    /// Returns bindings with no keys bound.
+    pub fn empty() -> KeyBindings {
+        KeyBindings { bindings: Vec::new() }
+    }
+
+    /// Binds `combo` to `action`, replacing any existing binding of `combo`.
+    pub fn bind(&mut self, combo: KeyCombo, action: Action) {
+        self.bindings.retain(|&(c, _)| c != combo);
+        self.bindings.push((combo, action));
+    }
+
+    /// Replaces the keys bound to an action, both given by name. `keys` is a
+    /// comma-separated list of key combos (see `KeyCombo::parse`) and may be
+    /// empty to unbind the action.
+    pub fn rebind(&mut self, action_name: &str, keys: &str) -> Result<(), String> {
+        let action = Action::from_name(action_name)
+            .ok_or_else(|| format!("unknown action: '{}'", action_name))?;
+        let mut combos = Vec::new();
+        for key in keys.split(',').map(|k| k.trim()).filter(|k| !k.is_empty()) {
+            combos.push(KeyCombo::parse(key).ok_or_else(|| format!("unknown key: '{}'", key))?);
+        }
+
+        self.bindings.retain(|&(_, a)| a != action);
+        for combo in combos {
+            self.bind(combo, action);
+        }
+        Ok(())
+    }
+
+    /// Returns the action bound to `code` pressed while holding `modifiers`.
+    /// Bindings requiring shift take precedence over those which don't.
+    pub fn action(&self, code: Vk, modifiers: ModifiersState) -> Option<Action> {
+        self.bindings.iter().filter(|&&(c, _)| c.matches(code, modifiers))
+            .max_by_key(|&&(c, _)| c.shift)
+            .map(|&(_, action)| action)
+    }
+
+    /// Returns the keys bound to `action`.
+    pub fn keys(&self, action: Action) -> Vec<KeyCombo> {
+        self.bindings.iter().filter(|&&(_, a)| a == action).map(|&(c, _)| c).collect()
+    }
+
+    /// Returns a (keys, description) row for each bound action, in the order
+    /// of `Action::all` followed by any other bound layers.
+    pub fn help_rows(&self) -> Vec<Vec<String>> {
+        let mut actions = Action::all();
+        for &(_, action) in self.bindings.iter() {
+            if !actions.contains(&action) { actions.push(action); }
+        }
+
+        actions.into_iter().filter_map(|action| {
+            let keys = self.keys(action);
+            if keys.is_empty() { return None; }
+            let labels: Vec<_> = keys.iter().map(|k| k.label()).collect();
+            Some(vec![labels.join(", "), action.description()])
+        }).collect()
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> KeyBindings {
+        use self::Action::*;
+
+        let mut bindings = KeyBindings::empty();
+        {
+            let mut bind = |combo, action| bindings.bind(combo, action);
+
+            bind(KeyCombo::new(Vk::Space), RunStop);
+            bind(KeyCombo::shift(Vk::Space), PauseResume);
+            bind(KeyCombo::new(Vk::Pause), PauseResume);
+            bind(KeyCombo::new(Vk::Period), Step);
+            bind(KeyCombo::shift(Vk::Equals), MoreIters);
+            bind(KeyCombo::new(Vk::Equals), MoreIters);
+            bind(KeyCombo::new(Vk::Add), MoreIters);
+            bind(KeyCombo::new(Vk::Minus), FewerIters);
+            bind(KeyCombo::new(Vk::Subtract), FewerIters);
+
+            let number_keys = [Vk::Key1, Vk::Key2, Vk::Key3, Vk::Key4, Vk::Key5, Vk::Key6,
+                Vk::Key7, Vk::Key8, Vk::Key9, Vk::Key0];
+            for (idx, &code) in number_keys.iter().enumerate().take(LAYER_KEY_COUNT) {
+                bind(KeyCombo::new(code), ToggleLayer(idx));
+            }
+
+            bind(KeyCombo::new(Vk::O), ViewOutput);
+            bind(KeyCombo::shift(Vk::O), ViewAll);
+            bind(KeyCombo::new(Vk::F), FitView);
+            bind(KeyCombo::new(Vk::V), ResetView);
+            bind(KeyCombo::new(Vk::W), ZoomIn);
+            bind(KeyCombo::new(Vk::S), ZoomOut);
+            bind(KeyCombo::new(Vk::A), PanLeft);
+            bind(KeyCombo::new(Vk::D), PanRight);
+            bind(KeyCombo::new(Vk::Left), OrbitLeft);
+            bind(KeyCombo::new(Vk::Right), OrbitRight);
+            bind(KeyCombo::new(Vk::Up), TiltUp);
+            bind(KeyCombo::new(Vk::Down), TiltDown);
+            bind(KeyCombo::new(Vk::F12), Screenshot);
+            bind(KeyCombo::new(Vk::R), ToggleRecording);
+            bind(KeyCombo::new(Vk::T), ToggleTractRecording);
+            bind(KeyCombo::new(Vk::L), ToggleLayerList);
+            bind(KeyCombo::new(Vk::N), ToggleStats);
+            bind(KeyCombo::new(Vk::P), TogglePlots);
+            bind(KeyCombo::new(Vk::H), ToggleHistograms);
+            bind(KeyCombo::new(Vk::F1), ToggleHud);
+
+            let hud_keys = [Vk::F2, Vk::F3, Vk::F4, Vk::F5, Vk::F6, Vk::F7];
+            for (idx, &code) in hud_keys.iter().enumerate().take(HUD_SECTION_KEY_COUNT) {
+                bind(KeyCombo::new(code), ToggleHudSection(idx));
+            }
+
+            bind(KeyCombo::new(Vk::F11), ToggleFullscreen);
+            bind(KeyCombo::new(Vk::M), NextDisplayMode);
+            bind(KeyCombo::new(Vk::Back), ResetHistory);
+            bind(KeyCombo::new(Vk::C), NextColorMap);
+            bind(KeyCombo::new(Vk::X), NextScale);
+            bind(KeyCombo::shift(Vk::Left), ReplayBack);
+            bind(KeyCombo::shift(Vk::Right), ReplayForward);
+            bind(KeyCombo::new(Vk::PageUp), ReplayPageBack);
+            bind(KeyCombo::new(Vk::PageDown), ReplayPageForward);
+            bind(KeyCombo::new(Vk::Home), ReplayStart);
+            bind(KeyCombo::new(Vk::End), ReplayEnd);
+            bind(KeyCombo::new(Vk::LBracket), ReplaySlower);
+            bind(KeyCombo::new(Vk::RBracket), ReplayFaster);
+            bind(KeyCombo::shift(Vk::Slash), ToggleHelp);
+            bind(KeyCombo::ctrl(Vk::Q), Exit);
+        }
+        bindings
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn action_names() {
+        for action in Action::all() {
+            assert_eq!(Action::from_name(&action.name()), Some(action));
+        }
+        assert_eq!(Action::from_name("toggle_layer_10"), Some(Action::ToggleLayer(9)));
+        assert_eq!(Action::from_name("toggle_layer_12"), Some(Action::ToggleLayer(11)));
+        assert_eq!(Action::from_name("toggle_layer_list"), Some(Action::ToggleLayerList));
+        assert_eq!(Action::from_name("toggle_layer_0"), None);
+        assert_eq!(Action::from_name("toggle_layer_01"), None);
+        assert_eq!(Action::from_name("toggle_layer_+1"), None);
+    }
+
+    #[test]
+    fn layer_keys() {
+        let mut bindings = KeyBindings::default();
+        assert_eq!(bindings.keys(Action::ToggleLayer(9)), vec![KeyCombo::new(Vk::Key0)]);
+
+        bindings.rebind("toggle_layer_11", "Shift+Key1").unwrap();
+        let rows = bindings.help_rows();
+        assert_eq!(rows.last().unwrap()[1], Action::ToggleLayer(10).description());
+    }
+
+    #[test]
+    fn rebind_errors() {
+        let mut bindings = KeyBindings::default();
+        assert_eq!(bindings.rebind("screen_shot", "F12"),
+            Err("unknown action: 'screen_shot'".to_owned()));
+        assert_eq!(bindings.rebind("screenshot", "F12, F13"),
+            Err("unknown key: 'F13'".to_owned()));
+        // Nothing is rebound on error:
+        assert_eq!(bindings.keys(Action::Screenshot),
+            KeyBindings::default().keys(Action::Screenshot));
+    }
+}
@@ -0,0 +1,113 @@
+//! A clickable checklist of layers used to show or hide their slices.
+
+use std::ops::Range;
+use glium::Surface;
+use window::{Overlay, HexGridBuffer};
+use window::overlay::{TEXT_SIZE, TEXT_COLOR};
+
+const PADDING: f32 = 6.0;
+const LINE_HEIGHT: f32 = TEXT_SIZE * 1.4;
+const BG_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const HIDDEN_COLOR: (f32, f32, f32, f32) = (0.55, 0.55, 0.55, 1.0);
+
+
+/// A layer checklist, labeled with the tags of each layer.
+pub struct LayerList {
+    shown: bool,
+    /// The screen rectangle (position, size) and slice range of each item,
+    /// as last drawn.
+    items: Vec<((f32, f32), (f32, f32), Range<usize>)>,
+    /// The screen rectangle (position, size) of the list, as last drawn.
+    rect: Option<((f32, f32), (f32, f32))>,
+    /// The position of the list's top left corner, as last drawn.
+    pos: (f32, f32),
+}
+
+impl LayerList {
+    pub fn new() -> LayerList {
+        LayerList { shown: true, items: Vec::new(), rect: None, pos: (10.0, 10.0) }
+    }
+
+    pub fn toggle_shown(&mut self) {
+        self.shown = !self.shown;
+    }
+
+    /// Draws the list with its top left corner at `pos`.
+    pub fn draw<S: Surface>(&mut self, overlay: &Overlay, target: &mut S, pos: (f32, f32),
+            buffer: &HexGridBuffer)
+    {
+        self.items.clear();
+        self.rect = None;
+        self.pos = pos;
+        if !self.shown { return; }
+
+        let layers = buffer.layout().layers();
+        let lines: Vec<_> = layers.iter().rev().map(|&(tags, ref slc_range)| {
+            let checked = slc_range.clone().any(|slc_id| buffer.is_slc_visible(slc_id));
+            let slcs = if slc_range.len() == 1 {
+                format!("slice {}", slc_range.start)
+            } else {
+                format!("slices {}-{}", slc_range.start, slc_range.end - 1)
+            };
+            (format!("[{}] {} ({})", if checked { "x" } else { " " }, tags, slcs), checked,
+                slc_range.clone())
+        }).collect();
+
+        let width = lines.iter().map(|l| overlay.text_width(&l.0, TEXT_SIZE))
+            .fold(0.0f32, |a, b| a.max(b)) + PADDING * 2.0;
+        let height = LINE_HEIGHT * lines.len() as f32 + PADDING * 2.0;
+        overlay.draw_rect(target, pos, (width, height), BG_COLOR);
+        self.rect = Some((pos, (width, height)));
+
+        for (i, (text, checked, slc_range)) in lines.into_iter().enumerate() {
+            let top = pos.1 + PADDING + LINE_HEIGHT * i as f32;
+            let color = if checked { TEXT_COLOR } else { HIDDEN_COLOR };
+            overlay.draw_text(target, &text, (pos.0 + PADDING, top + LINE_HEIGHT * 0.7),
+                TEXT_SIZE, color);
+            self.items.push(((pos.0, top), (width, LINE_HEIGHT), slc_range));
+        }
+    }
+
+    /// Returns the position of the list's top right corner, as last drawn
+    /// (or where it would be if hidden).
+    pub fn top_right(&self) -> (f32, f32) {
+        match self.rect {
+            Some((pos, size)) => (pos.0 + size.0, pos.1),
+            None => (0.0, self.pos.1),
+        }
+    }
+
+    /// Toggles the layer under `pos` (in pixels), if any. Returns true if the
+    /// click landed on the list.
+    pub fn click(&self, pos: (i32, i32), buffer: &mut HexGridBuffer) -> bool {
+        let (x, y) = (pos.0 as f32, pos.1 as f32);
+
+        for &(item_pos, item_size, ref slc_range) in self.items.iter() {
+            if x >= item_pos.0 && x < item_pos.0 + item_size.0 &&
+                    y >= item_pos.1 && y < item_pos.1 + item_size.1
+            {
+                toggle_slcs(slc_range.clone(), buffer);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Toggles the `idx`th layer of the list (counting from the top).
+    /// Returns false if there is no such layer.
+    pub fn toggle_layer(idx: usize, buffer: &mut HexGridBuffer) -> bool {
+        let slc_range = match buffer.layout().layers().into_iter().rev().nth(idx) {
+            Some((_, slc_range)) => slc_range,
+            None => return false,
+        };
+        toggle_slcs(slc_range, buffer);
+        true
+    }
+}
+
+
+/// Hides the slices in `slc_range` if any are visible, otherwise shows them.
+fn toggle_slcs(slc_range: Range<usize>, buffer: &mut HexGridBuffer) {
+    let any_visible = slc_range.clone().any(|slc_id| buffer.is_slc_visible(slc_id));
+    buffer.set_slcs_visible(slc_range, !any_visible);
+}
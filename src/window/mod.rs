@@ -2,11 +2,43 @@
 mod window;
 mod hex_grid_buffer;
 mod hex_grid;
-mod status_text;
+mod overlay;
+mod selection;
+mod screenshot;
+mod recorder;
+mod tract_layout;
+mod tract_stream;
+mod replay;
+mod area;
+mod layer_list;
+mod color_map;
+mod history;
+mod stats;
+mod plot;
+mod histogram;
+mod hud;
+mod keys;
+mod speed;
 
-pub use self::window::{Window, WindowStats};
-pub use self::hex_grid_buffer::{HexGridBuffer, StateVertex};
-pub use self::hex_grid::HexGrid;
-pub use self::status_text::StatusText;
+pub use self::window::{Window, WindowStats, WindowSettings, Source, AreaView};
+pub use self::hex_grid_buffer::{HexGridBuffer, StateVertex, HighlightVertex, Highlight};
+pub use self::hex_grid::{HexGrid, HexPick};
+pub use self::overlay::Overlay;
+pub use self::recorder::Recorder;
+pub use self::tract_layout::{TractLayout, SliceLayout};
+pub use self::tract_stream::{TractWriter, TractReader};
+pub use self::replay::Replay;
+pub use self::area::{AreaDesc, describe_areas};
+pub use self::layer_list::LayerList;
+pub use self::color_map::{ColorMap, ValueScale, Encoding};
+pub use self::history::{ActivityHistory, DisplayMode};
+pub use self::stats::{SliceStats, StatsTable};
+pub use self::plot::PlotPanel;
+pub use self::histogram::HistogramPanel;
+pub use self::hud::{Hud, HudProvider, CameraHud, TimingHud, AreaHud, LayersHud, StatsHud,
+    PickedCellHud};
+pub use self::keys::{KeyBindings, KeyCombo, Action};
+pub use self::speed::{SpeedLimit, Throttle};
+pub use self::selection::{Selection, CellLayerInfo, SynBuffer, cell_layers};
 
 // pub const MAX_GRID_SIZE: u32 = 8192;
@@ -0,0 +1,228 @@
+//! Screen-space (pixel coordinate) shapes and text drawn over the scene.
+
+use glium_text_rusttype::{self, TextSystem, FontTexture, TextDisplay};
+use glium::backend::glutin::Display;
+use glium::{self, Surface, Program, DrawParameters, VertexBuffer};
+use glium::index::{NoIndices, PrimitiveType};
+
+/// Font size in pixels used when none is specified.
+pub const TEXT_SIZE: f32 = 16.0;
+pub const TEXT_COLOR: (f32, f32, f32, f32) = (0.99, 0.99, 0.99, 1.0);
+const TOOLTIP_BG_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.75];
+const TOOLTIP_PADDING: f32 = 6.0;
+const LINE_SPACING: f32 = 1.4;
+const TABLE_COL_SPACING: f32 = 14.0;
+const TABLE_HEADER_COLOR: (f32, f32, f32, f32) = (0.65, 0.80, 0.99, 1.0);
+
+
+#[derive(Copy, Clone)]
+struct OverlayVertex {
+    position: [f32; 2],
+    color: [f32; 4],
+}
+implement_vertex!(OverlayVertex, position, color);
+
+
+/// Draws rectangles, lines and text in pixel coordinates (origin top left).
+pub struct Overlay<'d> {
+    display: Display,
+    program: Program,
+    params: DrawParameters<'d>,
+    text_system: TextSystem,
+    font_texture: FontTexture,
+}
+
+impl<'d> Overlay<'d> {
+    pub fn new(display: &Display) -> Overlay<'d> {
+        let program = Program::from_source(display, vertex_shader_src, fragment_shader_src,
+            None).unwrap();
+
+        let params = DrawParameters {
+            blend: glium::Blend::alpha_blending(),
+            .. Default::default()
+        };
+
+        let text_system = TextSystem::new(display);
+        let font_texture = FontTexture::new(display, &include_bytes!(
+                "assets/fonts/NotoSans/NotoSans-Regular.ttf"
+            )[..], 36, FontTexture::ascii_character_list()).unwrap();
+
+        Overlay {
+            display: display.clone(),
+            program: program,
+            params: params,
+            text_system: text_system,
+            font_texture: font_texture,
+        }
+    }
+
+    fn draw_vertices<S: Surface>(&self, target: &mut S, vertices: &[OverlayVertex],
+            primitive: PrimitiveType)
+    {
+        if vertices.is_empty() { return; }
+        let (width, height) = target.get_dimensions();
+        let vbo = VertexBuffer::new(&self.display, vertices).unwrap();
+        let uniforms = uniform! { surface_dims: [width as f32, height as f32] };
+        target.draw(&vbo, &NoIndices(primitive), &self.program, &uniforms, &self.params)
+            .unwrap();
+    }
+
+    /// Draws a filled rectangle with its top left corner at `pos`.
+    pub fn draw_rect<S: Surface>(&self, target: &mut S, pos: (f32, f32), size: (f32, f32),
+            color: [f32; 4])
+    {
+        let (x0, y0, x1, y1) = (pos.0, pos.1, pos.0 + size.0, pos.1 + size.1);
+        let v = |x, y| OverlayVertex { position: [x, y], color: color };
+        self.draw_vertices(target, &[v(x0, y0), v(x1, y0), v(x0, y1), v(x1, y1)],
+            PrimitiveType::TriangleStrip);
+    }
+
+    /// Draws a connected line through `points`.
+    pub fn draw_line_strip<S: Surface>(&self, target: &mut S, points: &[(f32, f32)],
+            color: [f32; 4])
+    {
+        let vertices: Vec<_> = points.iter()
+            .map(|&(x, y)| OverlayVertex { position: [x, y], color: color })
+            .collect();
+        self.draw_vertices(target, &vertices, PrimitiveType::LineStrip);
+    }
+
+    /// Draws `text` with its baseline starting at `pos`.
+    pub fn draw_text<S: Surface>(&self, target: &mut S, text: &str, pos: (f32, f32),
+            size: f32, color: (f32, f32, f32, f32))
+    {
+        let (width, height) = target.get_dimensions();
+        let text_x_scl = size * 2.0 / width as f32;
+        let text_y_scl = size * 2.0 / height as f32;
+
+        let text_xform = [
+            [text_x_scl, 0.0, 0.0, 0.0,],
+            [0.0, text_y_scl, 0.0, 0.0,],
+            [0.0, 0.0, 1.0, 0.0,],
+            [-1.0 + (pos.0 * 2.0 / width as f32), 1.0 - (pos.1 * 2.0 / height as f32), 0.0, 1.0f32,],
+        ];
+
+        let text_display = TextDisplay::new(&self.text_system, &self.font_texture, text);
+
+        glium_text_rusttype::draw(&text_display, &self.text_system, target, text_xform, color)
+            .unwrap();
+    }
+
+    /// Returns the width of `text` in pixels.
+    pub fn text_width(&self, text: &str, size: f32) -> f32 {
+        TextDisplay::new(&self.text_system, &self.font_texture, text).get_width() * size
+    }
+
+    /// Returns the size of a box drawn by `::draw_text_box`.
+    pub fn text_box_size(&self, lines: &[String]) -> (f32, f32) {
+        let text_width = lines.iter().map(|l| self.text_width(l, TEXT_SIZE))
+            .fold(0.0f32, |a, b| a.max(b));
+        (text_width + TOOLTIP_PADDING * 2.0,
+            TEXT_SIZE * LINE_SPACING * lines.len() as f32 + TOOLTIP_PADDING * 2.0)
+    }
+
+    /// Draws a box containing `lines` with its top left corner at `pos`.
+    pub fn draw_text_box<S: Surface>(&self, target: &mut S, pos: (f32, f32), lines: &[String]) {
+        let line_height = TEXT_SIZE * LINE_SPACING;
+
+        self.draw_rect(target, pos, self.text_box_size(lines), TOOLTIP_BG_COLOR);
+
+        for (i, line) in lines.iter().enumerate() {
+            let y = pos.1 + TOOLTIP_PADDING + line_height * (i as f32 + 1.0) - line_height * 0.3;
+            self.draw_text(target, line, (pos.0 + TOOLTIP_PADDING, y), TEXT_SIZE, TEXT_COLOR);
+        }
+    }
+
+    /// Returns the size of a table drawn by `::draw_table`.
+    pub fn table_size(&self, rows: &[Vec<String>]) -> (f32, f32) {
+        let col_widths = self.table_col_widths(rows);
+        (col_widths.iter().sum::<f32>() + TABLE_COL_SPACING * col_widths.len().saturating_sub(1)
+                as f32 + TOOLTIP_PADDING * 2.0,
+            TEXT_SIZE * LINE_SPACING * rows.len() as f32 + TOOLTIP_PADDING * 2.0)
+    }
+
+    /// Draws a box containing a table, with columns aligned, with its top
+    /// left corner at `pos`. The first row is drawn as a header.
+    pub fn draw_table<S: Surface>(&self, target: &mut S, pos: (f32, f32), rows: &[Vec<String>]) {
+        let line_height = TEXT_SIZE * LINE_SPACING;
+        let col_widths = self.table_col_widths(rows);
+
+        self.draw_rect(target, pos, self.table_size(rows), TOOLTIP_BG_COLOR);
+
+        for (i, row) in rows.iter().enumerate() {
+            let y = pos.1 + TOOLTIP_PADDING + line_height * (i as f32 + 1.0) - line_height * 0.3;
+            let color = if i == 0 { TABLE_HEADER_COLOR } else { TEXT_COLOR };
+            let mut x = pos.0 + TOOLTIP_PADDING;
+
+            for (cell, col_width) in row.iter().zip(col_widths.iter()) {
+                self.draw_text(target, cell, (x, y), TEXT_SIZE, color);
+                x += col_width + TABLE_COL_SPACING;
+            }
+        }
+    }
+
+    fn table_col_widths(&self, rows: &[Vec<String>]) -> Vec<f32> {
+        let mut col_widths: Vec<f32> = Vec::new();
+        for row in rows {
+            for (col, cell) in row.iter().enumerate() {
+                let width = self.text_width(cell, TEXT_SIZE);
+                if col < col_widths.len() {
+                    col_widths[col] = col_widths[col].max(width);
+                } else {
+                    col_widths.push(width);
+                }
+            }
+        }
+        col_widths
+    }
+
+    /// Draws a box containing `lines` next to `pos` (usually the mouse
+    /// position), keeping it within the surface.
+    pub fn draw_tooltip<S: Surface>(&self, target: &mut S, pos: (f32, f32), lines: &[String]) {
+        let (width, height) = target.get_dimensions();
+        let box_size = self.text_box_size(lines);
+
+        // Offset from the cursor, flipping to the other side near the edges:
+        let mut box_pos = (pos.0 + 16.0, pos.1 + 16.0);
+        if box_pos.0 + box_size.0 > width as f32 { box_pos.0 = pos.0 - 16.0 - box_size.0; }
+        if box_pos.1 + box_size.1 > height as f32 { box_pos.1 = pos.1 - 16.0 - box_size.1; }
+
+        self.draw_text_box(target, box_pos, lines);
+    }
+}
+
+
+// Vertex Shader:
+#[allow(non_upper_case_globals)]
+static vertex_shader_src: &'static str = r#"
+    #version 330
+
+    in vec2 position;
+    in vec4 color;
+
+    out vec4 v_color;
+
+    uniform vec2 surface_dims;
+
+    void main() {
+        vec2 ndc = vec2((position.x / surface_dims.x) * 2.0 - 1.0,
+            1.0 - (position.y / surface_dims.y) * 2.0);
+        gl_Position = vec4(ndc, 0.0, 1.0);
+        v_color = color;
+    }
+"#;
+
+
+// Fragment Shader:
+#[allow(non_upper_case_globals)]
+static fragment_shader_src: &'static str = r#"
+    #version 330
+
+    in vec4 v_color;
+
+    out vec4 color;
+
+    void main() {
+        color = v_color;
+    }
+"#;
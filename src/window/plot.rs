@@ -0,0 +1,214 @@
+//! Scrolling time series plots.
+
+use std::collections::VecDeque;
+use glium::Surface;
+use window::Overlay;
+use window::overlay::TEXT_COLOR;
+
+/// Number of samples kept (and shown) for each series by default.
+pub const DEFAULT_WINDOW_LEN: usize = 300;
+
+const PLOT_SIZE: (f32, f32) = (360.0, 80.0);
+const PLOT_SPACING: f32 = 10.0;
+const PADDING: f32 = 6.0;
+const LABEL_SIZE: f32 = 13.0;
+const BG_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const FRAME_COLOR: [f32; 4] = [0.5, 0.5, 0.5, 0.8];
+
+/// Colors assigned to series in the order they are added to a plot.
+const SERIES_COLORS: [[f32; 4]; 8] = [
+    [0.99, 0.60, 0.10, 1.0],
+    [0.30, 0.75, 0.99, 1.0],
+    [0.55, 0.90, 0.30, 1.0],
+    [0.95, 0.35, 0.55, 1.0],
+    [0.75, 0.55, 0.99, 1.0],
+    [0.99, 0.90, 0.30, 1.0],
+    [0.35, 0.95, 0.85, 1.0],
+    [0.85, 0.85, 0.85, 1.0],
+];
+
+
+/// A named sequence of (cycle, value) samples.
+struct Series {
+    name: String,
+    color: [f32; 4],
+    samples: VecDeque<(u32, f32)>,
+}
+
+
+/// A set of series sharing axes.
+struct Plot {
+    title: String,
+    series: Vec<Series>,
+}
+
+impl Plot {
+    /// Returns the (cycle, value) ranges spanned by every series, with the
+    /// value range including zero.
+    fn bounds(&self) -> Option<((u32, u32), (f32, f32))> {
+        let mut samples = self.series.iter().flat_map(|s| s.samples.iter());
+        let first = match samples.next() {
+            Some(&s) => s,
+            None => return None,
+        };
+
+        let ((mut c0, mut c1), (mut v0, mut v1)) = ((first.0, first.0), (first.1.min(0.0),
+            first.1.max(0.0)));
+        for &(cycle, value) in samples {
+            c0 = c0.min(cycle);
+            c1 = c1.max(cycle);
+            v0 = v0.min(value);
+            v1 = v1.max(value);
+        }
+        Some(((c0, c1), (v0, v1)))
+    }
+}
+
+
+/// A panel of scrolling plots with autoscaled axes.
+pub struct PlotPanel {
+    shown: bool,
+    window_len: usize,
+    plots: Vec<Plot>,
+}
+
+impl PlotPanel {
+    pub fn new(window_len: usize) -> PlotPanel {
+        PlotPanel { shown: true, window_len: window_len.max(2), plots: Vec::new() }
+    }
+
+    pub fn toggle_shown(&mut self) {
+        self.shown = !self.shown;
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.shown
+    }
+
+    /// Adds a sample to a series, creating the plot and series as needed.
+    /// Plots are drawn in the order they were created.
+    pub fn push(&mut self, plot_title: &str, series_name: &str, cycle: u32, value: f32) {
+        let plot_idx = match self.plots.iter().position(|p| p.title == plot_title) {
+            Some(idx) => idx,
+            None => {
+                self.plots.push(Plot { title: plot_title.to_owned(), series: Vec::new() });
+                self.plots.len() - 1
+            },
+        };
+        let plot = &mut self.plots[plot_idx];
+
+        let series_idx = match plot.series.iter().position(|s| s.name == series_name) {
+            Some(idx) => idx,
+            None => {
+                let color = SERIES_COLORS[plot.series.len() % SERIES_COLORS.len()];
+                plot.series.push(Series { name: series_name.to_owned(), color: color,
+                    samples: VecDeque::with_capacity(self.window_len) });
+                plot.series.len() - 1
+            },
+        };
+        let samples = &mut plot.series[series_idx].samples;
+
+        // Replace samples from the same cycle (or from before a restart):
+        while samples.back().map(|&(c, _)| c >= cycle).unwrap_or(false) {
+            samples.pop_back();
+        }
+        if samples.len() >= self.window_len { samples.pop_front(); }
+        samples.push_back((cycle, value));
+    }
+
+    /// Removes a series (and its plot if it was the last).
+    pub fn remove(&mut self, plot_title: &str, series_name: &str) {
+        for plot in self.plots.iter_mut().filter(|p| p.title == plot_title) {
+            plot.series.retain(|s| s.name != series_name);
+        }
+        self.plots.retain(|p| !p.series.is_empty());
+    }
+
+    /// Removes every plot.
+    pub fn clear(&mut self) {
+        self.plots.clear();
+    }
+
+    /// Returns the size of the panel when drawn.
+    pub fn size(&self) -> (f32, f32) {
+        if !self.shown || self.plots.is_empty() { return (0.0, 0.0); }
+        let plot_height = PLOT_SIZE.1 + LABEL_SIZE * 2.0;
+        (PLOT_SIZE.0 + PADDING * 2.0, (plot_height + PLOT_SPACING) * self.plots.len() as f32
+            - PLOT_SPACING + PADDING * 2.0)
+    }
+
+    /// Draws the panel with its top left corner at `pos`.
+    pub fn draw<S: Surface>(&self, overlay: &Overlay, target: &mut S, pos: (f32, f32)) {
+        if !self.shown || self.plots.is_empty() { return; }
+
+        overlay.draw_rect(target, pos, self.size(), BG_COLOR);
+
+        let mut y = pos.1 + PADDING;
+        for plot in self.plots.iter() {
+            self.draw_plot(overlay, target, plot, (pos.0 + PADDING, y));
+            y += PLOT_SIZE.1 + LABEL_SIZE * 2.0 + PLOT_SPACING;
+        }
+    }
+
+    fn draw_plot<S: Surface>(&self, overlay: &Overlay, target: &mut S, plot: &Plot,
+            pos: (f32, f32))
+    {
+        // Title and series names (in their colors) above the plot:
+        let label_y = pos.1 + LABEL_SIZE;
+        overlay.draw_text(target, &plot.title, (pos.0, label_y), LABEL_SIZE, TEXT_COLOR);
+        let mut name_x = pos.0 + overlay.text_width(&plot.title, LABEL_SIZE) + 12.0;
+        if plot.series.len() > 1 {
+            for series in plot.series.iter() {
+                let c = series.color;
+                overlay.draw_text(target, &series.name, (name_x, label_y), LABEL_SIZE,
+                    (c[0], c[1], c[2], c[3]));
+                name_x += overlay.text_width(&series.name, LABEL_SIZE) + 8.0;
+            }
+        }
+
+        let (x0, y0) = (pos.0, pos.1 + LABEL_SIZE * 1.4);
+        let (x1, y1) = (x0 + PLOT_SIZE.0, y0 + PLOT_SIZE.1);
+        overlay.draw_line_strip(target, &[(x0, y0), (x1, y0), (x1, y1), (x0, y1), (x0, y0)],
+            FRAME_COLOR);
+
+        let ((c0, c1), (v0, v1)) = match plot.bounds() {
+            Some(bounds) => bounds,
+            None => return,
+        };
+        let c_span = (c1 - c0).max(1) as f32;
+        let v_span = if v1 > v0 { v1 - v0 } else { 1.0 };
+
+        for series in plot.series.iter() {
+            let points: Vec<_> = series.samples.iter().map(|&(cycle, value)| {
+                (x0 + (cycle - c0) as f32 / c_span * PLOT_SIZE.0,
+                    y1 - (value - v0) / v_span * PLOT_SIZE.1)
+            }).collect();
+            overlay.draw_line_strip(target, &points, series.color);
+        }
+
+        // Axis labels (value range on the right, cycle range below):
+        let max_label = format_value(v1);
+        overlay.draw_text(target, &max_label, (x1 - overlay.text_width(&max_label, LABEL_SIZE)
+            - 3.0, y0 + LABEL_SIZE), LABEL_SIZE, TEXT_COLOR);
+        let min_label = format_value(v0);
+        overlay.draw_text(target, &min_label, (x1 - overlay.text_width(&min_label, LABEL_SIZE)
+            - 3.0, y1 - 3.0), LABEL_SIZE, TEXT_COLOR);
+
+        let last_label = format!("cycle {}", c1);
+        overlay.draw_text(target, &c0.to_string(), (x0, y1 + LABEL_SIZE), LABEL_SIZE, TEXT_COLOR);
+        overlay.draw_text(target, &last_label, (x1 - overlay.text_width(&last_label, LABEL_SIZE),
+            y1 + LABEL_SIZE), LABEL_SIZE, TEXT_COLOR);
+    }
+}
+
+
+/// Formats an axis value with a precision suited to its magnitude.
+fn format_value(value: f32) -> String {
+    if value == 0.0 || value.abs() >= 100.0 {
+        format!("{:.0}", value)
+    } else if value.abs() >= 1.0 {
+        format!("{:.1}", value)
+    } else {
+        format!("{:.3}", value)
+    }
+}
@@ -0,0 +1,229 @@
+//! Records the hex grids to a numbered image sequence, optionally piping it
+//! to an encoder.
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use time;
+use image::ColorType;
+use image::png::PNGEncoder;
+use window::screenshot::Image;
+
+
+/// A recording in progress.
+///
+/// Frames are saved as `frame_000000.png`, `frame_000001.png`, ... within a
+/// new timestamped directory. When an encoder command is given, frames are
+/// instead written as PNG data to its stdin (e.g. `ffmpeg -f image2pipe -i -
+/// out.mp4`), with the recording directory as its working directory. Either
+/// way, `cycles.txt` lists the cycle number of each frame.
+///
+/// Frames are rendered offscreen (see `screenshot::render_offscreen`), so
+/// they contain the hex grids only.
+pub struct Recorder {
+    dir: PathBuf,
+    every: u32,
+    next_cycle: u32,
+    frame_count: u32,
+    cycles_file: BufWriter<File>,
+    encoder: Option<Child>,
+}
+
+impl Recorder {
+    /// Starts a new recording within `output_dir`, capturing a frame every
+    /// `every` cycles starting at `cycle`.
+    ///
+    /// `encoder_cmd` is split into a program and its arguments as a shell
+    /// would (see `split_command`), without being run by a shell.
+    pub fn start(output_dir: &Path, every: u32, cycle: u32, encoder_cmd: Option<&str>)
+            -> Result<Recorder, String>
+    {
+        let timestamp = time::strftime("%Y%m%d-%H%M%S", &time::now())
+            .map_err(|e| format!("Error formatting timestamp: {}", e))?;
+        let dir = output_dir.join(format!("vibi_rec_{}", timestamp));
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Error creating '{}': {}", dir.display(), e))?;
+
+        let cycles_file = File::create(dir.join("cycles.txt"))
+            .map(BufWriter::new)
+            .map_err(|e| format!("Error creating 'cycles.txt': {}", e))?;
+
+        let encoder = match encoder_cmd {
+            Some(cmd) => {
+                let parts = split_command(cmd)?;
+                let (program, args) = parts.split_first()
+                    .ok_or("Empty encoder command.".to_owned())?;
+                let child = Command::new(program)
+                    .args(args)
+                    .current_dir(&dir)
+                    .stdin(Stdio::piped())
+                    .spawn()
+                    .map_err(|e| format!("Error starting encoder '{}': {}", program, e))?;
+                Some(child)
+            },
+            None => None,
+        };
+
+        Ok(Recorder {
+            dir: dir,
+            every: every.max(1),
+            next_cycle: cycle,
+            frame_count: 0,
+            cycles_file: cycles_file,
+            encoder: encoder,
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn frame_count(&self) -> u32 {
+        self.frame_count
+    }
+
+    /// Returns true if a frame is due at `cycle`.
+    pub fn frame_due(&self, cycle: u32) -> bool {
+        cycle >= self.next_cycle
+    }
+
+    /// Adds `image` as the frame for `cycle`.
+    ///
+    /// Frames are captured at most once per rendered frame, so when cycling
+    /// outpaces rendering, consecutive frames may be more than `every` cycles
+    /// apart (see `cycles.txt`).
+    pub fn capture(&mut self, image: &Image, cycle: u32) -> Result<(), String> {
+        match self.encoder {
+            Some(ref mut child) => {
+                let stdin = child.stdin.as_mut().ok_or("Encoder stdin unavailable.".to_owned())?;
+                PNGEncoder::new(stdin)
+                    .encode(image, image.width(), image.height(), ColorType::RGBA(8))
+                    .map_err(|e| format!("Error writing to encoder: {}", e))?;
+            },
+            None => {
+                let path = self.dir.join(format!("frame_{:06}.png", self.frame_count));
+                image.save(&path)
+                    .map_err(|e| format!("Error saving '{}': {}", path.display(), e))?;
+            },
+        }
+
+        writeln!(self.cycles_file, "{} {}", self.frame_count, cycle)
+            .map_err(|e| format!("Error writing 'cycles.txt': {}", e))?;
+
+        self.frame_count += 1;
+        self.next_cycle = cycle + self.every;
+        Ok(())
+    }
+
+    /// Ends the recording, waiting for the encoder (if any) to finish.
+    pub fn finish(mut self) -> Result<(), String> {
+        self.cycles_file.flush()
+            .map_err(|e| format!("Error writing 'cycles.txt': {}", e))?;
+
+        if let Some(mut child) = self.encoder.take() {
+            // Closing stdin signals the end of the stream:
+            drop(child.stdin.take());
+            let status = child.wait().map_err(|e| format!("Error waiting for encoder: {}", e))?;
+            if !status.success() {
+                return Err(format!("Encoder exited with: {}", status));
+            }
+        }
+        Ok(())
+    }
+}
+
+
+/// Splits a command line into words as a POSIX shell would, honoring single
+/// quotes, double quotes and backslash escapes (but nothing else).
+pub fn split_command(cmd: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = cmd.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("Unterminated single quote in command.".to_owned()),
+                    }
+                }
+            },
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ '"') | Some(c @ '\\') | Some(c @ '$') | Some(c @ '`') => {
+                                word.push(c)
+                            },
+                            Some(c) => { word.push('\\'); word.push(c); },
+                            None => return Err("Unterminated double quote in command.".to_owned()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("Unterminated double quote in command.".to_owned()),
+                    }
+                }
+            },
+            '\\' => {
+                in_word = true;
+                match chars.next() {
+                    Some(c) => word.push(c),
+                    None => return Err("Trailing backslash in command.".to_owned()),
+                }
+            },
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(word.clone());
+                    word.clear();
+                    in_word = false;
+                }
+            },
+            c => {
+                in_word = true;
+                word.push(c);
+            },
+        }
+    }
+
+    if in_word { words.push(word); }
+    Ok(words)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::split_command;
+
+    fn split(cmd: &str) -> Vec<String> {
+        split_command(cmd).unwrap()
+    }
+
+    #[test]
+    fn plain_words() {
+        assert_eq!(split("  ffmpeg -f image2pipe\t-i -  out.mp4 "),
+            vec!["ffmpeg", "-f", "image2pipe", "-i", "-", "out.mp4"]);
+        assert!(split("").is_empty());
+    }
+
+    #[test]
+    fn quotes_and_escapes() {
+        assert_eq!(split("'/my videos/ffmpeg' -i - \"out file.mp4\""),
+            vec!["/my videos/ffmpeg", "-i", "-", "out file.mp4"]);
+        assert_eq!(split("out\\ file.mp4 '' a\"b c\"d"), vec!["out file.mp4", "", "ab cd"]);
+        assert_eq!(split("\"say \\\"hi\\\" \\n\""), vec!["say \"hi\" \\n"]);
+    }
+
+    #[test]
+    fn unterminated() {
+        assert!(split_command("ffmpeg 'out.mp4").is_err());
+        assert!(split_command("ffmpeg \"out.mp4").is_err());
+        assert!(split_command("ffmpeg out\\").is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! Playback of tract stream files.
+
+use std::io;
+use std::path::Path;
+use time::{self, Timespec};
+use window::{TractReader, TractLayout};
+
+/// Frames per second played at a speed of 1.0.
+pub const BASE_FPS: f64 = 30.0;
+const MIN_SPEED: f64 = 1.0 / 16.0;
+const MAX_SPEED: f64 = 64.0;
+
+
+/// Plays back a tract stream file with play/pause, seek, and speed controls.
+pub struct Replay {
+    reader: TractReader,
+    frame_idx: usize,
+    /// Fractional position, in frames, advanced while playing.
+    position: f64,
+    playing: bool,
+    speed: f64,
+    last_update: Timespec,
+    /// Set when the current frame has changed but has not been read yet.
+    dirty: bool,
+}
+
+impl Replay {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Replay> {
+        let reader = TractReader::open(path)?;
+
+        Ok(Replay {
+            reader: reader,
+            frame_idx: 0,
+            position: 0.0,
+            playing: false,
+            speed: 1.0,
+            last_update: time::get_time(),
+            dirty: true,
+        })
+    }
+
+    pub fn area_name(&self) -> &str {
+        self.reader.area_name()
+    }
+
+    pub fn layout(&self) -> &TractLayout {
+        self.reader.layout()
+    }
+
+    pub fn frame_idx(&self) -> usize {
+        self.frame_idx
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.reader.frame_count()
+    }
+
+    /// Returns the cycle number of the current frame.
+    pub fn cycle(&self) -> u32 {
+        self.reader.frame_cycle(self.frame_idx).unwrap_or(0)
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    pub fn play(&mut self) {
+        // Start over when at the end:
+        if self.frame_idx + 1 >= self.frame_count() { self.seek(0); }
+        self.playing = true;
+        self.last_update = time::get_time();
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    pub fn toggle_play(&mut self) {
+        if self.playing { self.pause() } else { self.play() }
+    }
+
+    /// Jumps to a frame (clamped to the available frames).
+    pub fn seek(&mut self, frame_idx: usize) {
+        let frame_idx = frame_idx.min(self.frame_count().saturating_sub(1));
+        if frame_idx != self.frame_idx { self.dirty = true; }
+        self.frame_idx = frame_idx;
+        self.position = frame_idx as f64;
+    }
+
+    /// Moves forward (or backward) by `delta` frames.
+    pub fn seek_by(&mut self, delta: isize) {
+        let frame_idx = (self.frame_idx as isize + delta).max(0) as usize;
+        self.seek(frame_idx);
+    }
+
+    /// Jumps to a fraction (0.0 - 1.0) of the way through the file.
+    pub fn seek_fraction(&mut self, fraction: f64) {
+        let last = self.frame_count().saturating_sub(1) as f64;
+        self.seek((fraction.max(0.0).min(1.0) * last).round() as usize);
+    }
+
+    /// Multiplies the playback speed by `factor`.
+    pub fn scale_speed(&mut self, factor: f64) {
+        self.speed = (self.speed * factor).max(MIN_SPEED).min(MAX_SPEED);
+    }
+
+    /// Advances playback according to the time elapsed since the last call
+    /// and reads the current frame into `states` if it has changed.
+    ///
+    /// Returns true if `states` has been updated.
+    pub fn update(&mut self, states: &mut Vec<u8>) -> io::Result<bool> {
+        let now = time::get_time();
+
+        if self.playing {
+            let elapsed_secs = (now - self.last_update).num_microseconds()
+                .unwrap_or(0) as f64 / 1_000_000.0;
+            let last = self.frame_count().saturating_sub(1);
+            self.position = (self.position + elapsed_secs * BASE_FPS * self.speed)
+                .min(last as f64);
+
+            let frame_idx = self.position as usize;
+            if frame_idx != self.frame_idx {
+                self.frame_idx = frame_idx;
+                self.dirty = true;
+            }
+            if frame_idx >= last { self.playing = false; }
+        }
+        self.last_update = now;
+
+        if self.dirty && self.frame_count() > 0 {
+            self.reader.read_frame(self.frame_idx, states)?;
+            self.dirty = false;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Lines describing the playback state.
+    pub fn summary(&self) -> Vec<String> {
+        vec![
+            format!("Replay: {} [{}]", self.area_name(),
+                if self.playing { "playing" } else { "paused" }),
+            format!("Frame: {}/{} (cycle {})", self.frame_idx + 1, self.frame_count(),
+                self.cycle()),
+            format!("Speed: {}x", self.speed),
+        ]
+    }
+}
@@ -0,0 +1,69 @@
+//! Saves the hex grids to image files.
+
+use std::path::{Path, PathBuf};
+use time;
+use image::{self, ImageBuffer, Rgba};
+use glium::Surface;
+use glium::texture::{RawImage2d, Texture2d, UncompressedFloatFormat, MipmapsOption, DepthFormat};
+use glium::framebuffer::{SimpleFrameBuffer, DepthRenderBuffer};
+use glium::backend::glutin::Display;
+
+
+/// An RGBA image, top row first.
+pub type Image = ImageBuffer<Rgba<u8>, Vec<u8>>;
+
+
+/// Calls `draw` to render into an offscreen buffer of `dims`, cleared to
+/// `clear_color`, and reads the result back.
+///
+/// Used to capture the hex grids without the UI and overlays drawn on top of
+/// them in the window.
+pub fn render_offscreen<F>(display: &Display, dims: (u32, u32),
+        clear_color: (f32, f32, f32, f32), draw: F) -> Result<Image, String>
+        where F: FnOnce(&mut SimpleFrameBuffer)
+{
+    let color = Texture2d::empty_with_format(display, UncompressedFloatFormat::U8U8U8U8,
+        MipmapsOption::NoMipmap, dims.0, dims.1)
+        .map_err(|e| format!("Error creating offscreen texture: {:?}", e))?;
+    let depth = DepthRenderBuffer::new(display, DepthFormat::I24, dims.0, dims.1)
+        .map_err(|e| format!("Error creating offscreen depth buffer: {:?}", e))?;
+
+    {
+        let mut framebuffer = SimpleFrameBuffer::with_depth_buffer(display, &color, &depth)
+            .map_err(|e| format!("Error creating offscreen framebuffer: {:?}", e))?;
+        framebuffer.clear_color_and_depth(clear_color, 1.0);
+        draw(&mut framebuffer);
+    }
+
+    let raw: RawImage2d<u8> = color.read();
+    let image: Image = ImageBuffer::from_raw(raw.width, raw.height, raw.data.into_owned())
+        .ok_or("Invalid offscreen texture dimensions.".to_owned())?;
+
+    // OpenGL rows start at the bottom:
+    Ok(image::imageops::flip_vertical(&image))
+}
+
+/// Saves `image` to a timestamped PNG within `dir`, with `cycle` included in
+/// the file name.
+///
+/// The timestamp has millisecond resolution and a counter is appended if the
+/// name is taken anyway, so existing files are never overwritten. Returns the
+/// path of the new file.
+pub fn save_png(image: &Image, dir: &Path, cycle: u32) -> Result<PathBuf, String> {
+    let now = time::now();
+    let timestamp = time::strftime("%Y%m%d-%H%M%S", &now)
+        .map_err(|e| format!("Error formatting timestamp: {}", e))?;
+    let name = format!("vibi_{}-{:03}_cycle-{}", timestamp, now.tm_nsec / 1_000_000, cycle);
+
+    let mut path = dir.join(format!("{}.png", name));
+    let mut copy = 1;
+    while path.exists() {
+        copy += 1;
+        path = dir.join(format!("{}_{}.png", name, copy));
+    }
+
+    image.save(&path)
+        .map_err(|e| format!("Error saving '{}': {}", path.display(), e))?;
+
+    Ok(path)
+}
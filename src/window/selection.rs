@@ -0,0 +1,257 @@
+//! Cell selection.
+//!
+//! Clicking on a cell (an axon within a cellular layer) samples the synapse
+//! and dendrite buffers of that cell's layer and highlights the source axons
+//! of each of its synapses, their strengths, and which dendrites are active.
+
+use std::ops::Range;
+use bismit::{Cortex, TractReceiver, SamplerKind};
+use bismit::map::{LayerAddress, LayerKind};
+use window::{HexGridBuffer, HexPick, Highlight, TractLayout};
+
+
+/// Synapse and dendrite layout of a cellular layer.
+#[derive(Clone, Debug)]
+pub struct CellLayerInfo {
+    pub name: String,
+    pub addr: LayerAddress,
+    /// Slices containing the axons of the layer's cells.
+    pub slc_range: Range<usize>,
+    /// (dendrites per tuft, synapses per dendrite) for each tuft.
+    pub tfts: Vec<(usize, usize)>,
+}
+
+
+/// Returns the layers of an area which contain cells with synapses.
+///
+/// Must be called before the cortex is handed off to a flywheel.
+pub fn cell_layers(cortex: &Cortex, area_name: &str) -> Vec<CellLayerInfo> {
+    let area = match cortex.areas().by_key(area_name) {
+        Some(area) => area,
+        None => return Vec::new(),
+    };
+
+    area.area_map().layer_map().layers().iter().filter_map(|lyr| {
+        let cell_scheme = match *lyr.kind() {
+            LayerKind::Cellular(ref cell_scheme) => cell_scheme,
+            _ => return None,
+        };
+
+        let slc_range = lyr.slc_range()?;
+
+        let tfts: Vec<_> = cell_scheme.tft_schemes().iter()
+            .map(|tft| (1usize << tft.dens_per_tft_l2(), 1usize << tft.syns_per_den_l2()))
+            .collect();
+
+        // Control cells have no tufts:
+        if tfts.is_empty() { return None; }
+
+        Some(CellLayerInfo {
+            name: lyr.name().to_owned(),
+            addr: lyr.layer_addr(),
+            slc_range: (slc_range.start as usize)..(slc_range.end as usize),
+            tfts: tfts,
+        })
+    }).collect()
+}
+
+
+/// A sampled synapse or dendrite buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SynBuffer {
+    SrcSlcIds,
+    SrcColVOffs,
+    SrcColUOffs,
+    Strengths,
+    States,
+    DenStates,
+}
+
+impl SynBuffer {
+    /// Every buffer needed to describe a cell's synapses.
+    pub fn all() -> [SynBuffer; 6] {
+        [SynBuffer::SrcSlcIds, SynBuffer::SrcColVOffs, SynBuffer::SrcColUOffs,
+            SynBuffer::Strengths, SynBuffer::States, SynBuffer::DenStates]
+    }
+
+    pub fn sampler_kind(&self, addr: LayerAddress) -> SamplerKind {
+        match *self {
+            SynBuffer::SrcSlcIds => SamplerKind::SynSrcSlcIds(addr),
+            SynBuffer::SrcColVOffs => SamplerKind::SynSrcColVOffs(addr),
+            SynBuffer::SrcColUOffs => SamplerKind::SynSrcColUOffs(addr),
+            SynBuffer::Strengths => SamplerKind::SynStrengths(addr),
+            SynBuffer::States => SamplerKind::SynStates(addr),
+            SynBuffer::DenStates => SamplerKind::DenStates(addr),
+        }
+    }
+
+    fn idx(&self) -> usize {
+        *self as usize
+    }
+}
+
+
+/// A selected cell along with the sampled synapse data of its layer.
+pub struct Selection {
+    layer: CellLayerInfo,
+    pick: HexPick,
+    cel_idx: usize,
+    cel_count: usize,
+    rxs: Vec<Option<TractReceiver>>,
+    data: Vec<Vec<u8>>,
+    stale: bool,
+    summary: Vec<String>,
+}
+
+impl Selection {
+    /// Creates a new selection. Receivers for each `SynBuffer` must be added
+    /// with `::set_receiver` before anything is highlighted.
+    pub fn new(layer: CellLayerInfo, pick: HexPick, layout: &TractLayout) -> Selection {
+        let buf_count = SynBuffer::all().len();
+
+        let mut sel = Selection {
+            layer: layer,
+            pick: pick,
+            cel_idx: 0,
+            cel_count: 0,
+            rxs: (0..buf_count).map(|_| None).collect(),
+            data: vec![Vec::new(); buf_count],
+            stale: true,
+            summary: Vec::new(),
+        };
+        sel.reselect(pick, layout);
+        sel
+    }
+
+    /// Selects another cell within the same layer, keeping the samplers.
+    pub fn reselect(&mut self, pick: HexPick, layout: &TractLayout) {
+        let (v_size, u_size) = layout.slc_dims(pick.slc_id);
+        let slc_cols = (v_size * u_size) as usize;
+
+        self.cel_idx = (pick.slc_id - self.layer.slc_range.start) * slc_cols
+            + (pick.v_id * u_size + pick.u_id) as usize;
+        self.cel_count = self.layer.slc_range.len() * slc_cols;
+        self.pick = pick;
+        self.stale = true;
+    }
+
+    pub fn layer(&self) -> &CellLayerInfo {
+        &self.layer
+    }
+
+    pub fn pick(&self) -> HexPick {
+        self.pick
+    }
+
+    pub fn set_receiver(&mut self, buf: SynBuffer, rx: TractReceiver) {
+        self.rxs[buf.idx()] = Some(rx);
+    }
+
+    /// Reads any fresh synapse data and updates the highlighted axons.
+    pub fn refresh(&mut self, buffer: &mut HexGridBuffer) {
+        for (rx, data) in self.rxs.iter().zip(self.data.iter_mut()) {
+            if let Some(ref rx) = *rx {
+                // Never blocks (see `HexGridBuffer::refresh_vertex_buf`):
+                if let Some(read_buf) = rx.recv(false).wait().unwrap() {
+                    let read_guard = read_buf.read_u8().wait().unwrap();
+                    data.clear();
+                    data.extend_from_slice(read_guard.as_slice());
+                    self.stale = true;
+                }
+            }
+        }
+
+        if self.stale && self.data.iter().all(|d| !d.is_empty()) {
+            self.update_highlights(buffer);
+            self.stale = false;
+        }
+    }
+
+    /// Lines describing the selected cell.
+    pub fn summary(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("Selected: {} [slice: {}, v: {}, u: {}]", self.layer.name,
+                self.pick.slc_id, self.pick.v_id, self.pick.u_id),
+            format!("Cell: {}", self.cel_idx),
+        ];
+
+        if self.summary.is_empty() {
+            lines.push("Waiting for synapse data (sampled each cycle)...".to_owned());
+        } else {
+            lines.extend(self.summary.iter().cloned());
+        }
+        lines
+    }
+
+    fn update_highlights(&mut self, buffer: &mut HexGridBuffer) {
+        let mut highlights = vec![(self.pick.axn_idx, Highlight::Cell)];
+        let mut summary = Vec::with_capacity(self.layer.tfts.len());
+
+        {
+            let layout = buffer.layout();
+            let slc_id_range = layout.slc_id_range();
+            let buf = |b: SynBuffer| &self.data[b.idx()];
+
+            let mut den_base = 0;
+            let mut syn_base = 0;
+
+            for (tft_id, &(dens_per_tft, syns_per_den)) in self.layer.tfts.iter().enumerate() {
+                let mut dens_active = 0;
+                let mut syns_active = 0;
+
+                for den_id in 0..dens_per_tft {
+                    let den_idx = den_base + self.cel_idx * dens_per_tft + den_id;
+                    let den_active = buf(SynBuffer::DenStates).get(den_idx)
+                        .map(|&s| s > 0).unwrap_or(false);
+                    if den_active { dens_active += 1; }
+
+                    for syn_id in 0..syns_per_den {
+                        let syn_idx = syn_base + (self.cel_idx * dens_per_tft + den_id)
+                            * syns_per_den + syn_id;
+
+                        let (src_slc_id, v_ofs, u_ofs, strength, state) = match (
+                                buf(SynBuffer::SrcSlcIds).get(syn_idx),
+                                buf(SynBuffer::SrcColVOffs).get(syn_idx),
+                                buf(SynBuffer::SrcColUOffs).get(syn_idx),
+                                buf(SynBuffer::Strengths).get(syn_idx),
+                                buf(SynBuffer::States).get(syn_idx)) {
+                            (Some(&s), Some(&v), Some(&u), Some(&st), Some(&ss)) => {
+                                (s as usize, v as i8 as i32, u as i8 as i32, st as i8, ss)
+                            },
+                            _ => continue,
+                        };
+
+                        if state > 0 { syns_active += 1; }
+                        if src_slc_id < slc_id_range.start || src_slc_id >= slc_id_range.end {
+                            continue;
+                        }
+
+                        let (v_size, u_size) = layout.slc_dims(src_slc_id);
+                        let v_id = self.pick.v_id as i32 + v_ofs;
+                        let u_id = self.pick.u_id as i32 + u_ofs;
+                        if v_id < 0 || u_id < 0 || v_id >= v_size as i32 || u_id >= u_size as i32 {
+                            continue;
+                        }
+
+                        let axn_idx = layout.axn_id_range(src_slc_id..(src_slc_id + 1)).start
+                            + (v_id as usize * u_size as usize) + u_id as usize;
+
+                        highlights.push((axn_idx, Highlight::Source {
+                            strength: strength.max(0) as f32 / 127.0,
+                            active: den_active,
+                        }));
+                    }
+                }
+
+                summary.push(format!("Tuft {}: {}/{} dendrites active, {}/{} synapses active",
+                    tft_id, dens_active, dens_per_tft, syns_active, dens_per_tft * syns_per_den));
+
+                den_base += self.cel_count * dens_per_tft;
+                syn_base += self.cel_count * dens_per_tft * syns_per_den;
+            }
+        }
+
+        buffer.set_highlights(&highlights);
+        self.summary = summary;
+    }
+}
@@ -0,0 +1,214 @@
+//! Limits on how fast runs proceed, so that individual cycles can be seen.
+
+use time::{self, Timespec};
+
+
+/// How fast the flywheel may cycle while the window is displaying it.
+///
+/// Any limit makes the main sampler apply backpressure: the flywheel waits
+/// for each frame to be read before starting the next cycle, and the window
+/// reads frames no faster than the limit allows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpeedLimit {
+    /// As fast as the flywheel can cycle (frames are sampled as they are
+    /// drawn, skipping any cycles in between).
+    Unlimited,
+    /// One cycle per drawn frame.
+    OnePerFrame,
+    /// At most this many cycles per second (and at most one per frame).
+    CyclesPerSec(f32),
+}
+
+impl SpeedLimit {
+    /// Parses a limit as typed into the 'Speed:' box: "max" (or nothing) for
+    /// no limit, "frame" for one cycle per frame, or a number of cycles per
+    /// second (accepting a 'k' suffix).
+    pub fn parse(text: &str) -> Option<SpeedLimit> {
+        let text = text.trim().to_lowercase();
+        match text.as_str() {
+            "" | "max" | "unlimited" => return Some(SpeedLimit::Unlimited),
+            "f" | "frame" => return Some(SpeedLimit::OnePerFrame),
+            _ => (),
+        }
+
+        let (number, scale) = if text.ends_with('k') {
+            (&text[..text.len() - 1], 1000.0)
+        } else {
+            (text.as_str(), 1.0)
+        };
+        match number.parse::<f32>() {
+            Ok(cps) if cps > 0.0 && cps.is_finite() => Some(SpeedLimit::CyclesPerSec(cps * scale)),
+            _ => None,
+        }
+    }
+
+    /// Returns the limit as it would be typed (see `::parse`).
+    pub fn label(&self) -> String {
+        match *self {
+            SpeedLimit::Unlimited => "max".to_owned(),
+            SpeedLimit::OnePerFrame => "frame".to_owned(),
+            SpeedLimit::CyclesPerSec(cps) => format!("{}", cps),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match *self {
+            SpeedLimit::Unlimited => "None".to_owned(),
+            SpeedLimit::OnePerFrame => "1 cycle/frame".to_owned(),
+            SpeedLimit::CyclesPerSec(cps) => format!("{} cycles/s", cps),
+        }
+    }
+
+    /// Returns true if samplers should apply backpressure.
+    pub fn backpressure(&self) -> bool {
+        *self != SpeedLimit::Unlimited
+    }
+}
+
+impl Default for SpeedLimit {
+    fn default() -> SpeedLimit {
+        SpeedLimit::Unlimited
+    }
+}
+
+
+/// Paces the reading of frames according to a `SpeedLimit`.
+#[derive(Clone, Debug)]
+pub struct Throttle {
+    limit: SpeedLimit,
+    /// Cycles which may be read now (at most one).
+    budget: f64,
+    prev_time: Timespec,
+}
+
+impl Throttle {
+    pub fn new(limit: SpeedLimit) -> Throttle {
+        Throttle { limit: limit, budget: 1.0, prev_time: time::get_time() }
+    }
+
+    pub fn limit(&self) -> SpeedLimit {
+        self.limit
+    }
+
+    pub fn set_limit(&mut self, limit: SpeedLimit) {
+        self.limit = limit;
+        self.budget = 1.0;
+    }
+
+    /// Returns true if a frame may be read now. Called once per drawn frame.
+    pub fn ready(&mut self) -> bool {
+        let now = time::get_time();
+        let elapsed_secs = (now - self.prev_time).num_microseconds().unwrap_or(0) as f64
+            / 1000000.0;
+        self.prev_time = now;
+        self.refill(elapsed_secs)
+    }
+
+    /// Adds the cycles allowed by `elapsed_secs` to the budget and returns
+    /// true if a frame may be read.
+    fn refill(&mut self, elapsed_secs: f64) -> bool {
+        match self.limit {
+            SpeedLimit::Unlimited | SpeedLimit::OnePerFrame => true,
+            SpeedLimit::CyclesPerSec(cps) => {
+                // Unused time doesn't carry over (beyond one frame's worth):
+                self.budget = (self.budget + cps as f64 * elapsed_secs).min(1.0);
+                self.budget >= 1.0
+            },
+        }
+    }
+
+    /// Records that a frame was read.
+    pub fn consume(&mut self) {
+        if let SpeedLimit::CyclesPerSec(_) = self.limit {
+            self.budget -= 1.0;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(SpeedLimit::parse("max"), Some(SpeedLimit::Unlimited));
+        assert_eq!(SpeedLimit::parse(" "), Some(SpeedLimit::Unlimited));
+        assert_eq!(SpeedLimit::parse("Frame"), Some(SpeedLimit::OnePerFrame));
+        assert_eq!(SpeedLimit::parse("f"), Some(SpeedLimit::OnePerFrame));
+        assert_eq!(SpeedLimit::parse("30"), Some(SpeedLimit::CyclesPerSec(30.0)));
+        assert_eq!(SpeedLimit::parse("0.5"), Some(SpeedLimit::CyclesPerSec(0.5)));
+        assert_eq!(SpeedLimit::parse("2.5k"), Some(SpeedLimit::CyclesPerSec(2500.0)));
+
+        for text in ["0", "-1", "nan", "inf", "k", "fast", "2.5kk"].iter() {
+            assert_eq!(SpeedLimit::parse(text), None, "'{}'", text);
+        }
+    }
+
+    #[test]
+    fn labels_parse() {
+        for &limit in [SpeedLimit::Unlimited, SpeedLimit::OnePerFrame,
+                SpeedLimit::CyclesPerSec(12.5)].iter() {
+            assert_eq!(SpeedLimit::parse(&limit.label()), Some(limit));
+        }
+    }
+
+    #[test]
+    fn backpressure() {
+        assert!(!SpeedLimit::Unlimited.backpressure());
+        assert!(SpeedLimit::OnePerFrame.backpressure());
+        assert!(SpeedLimit::CyclesPerSec(1.0).backpressure());
+    }
+
+    #[test]
+    fn unthrottled() {
+        for &limit in [SpeedLimit::Unlimited, SpeedLimit::OnePerFrame].iter() {
+            let mut throttle = Throttle::new(limit);
+            for _ in 0..3 {
+                assert!(throttle.refill(0.0));
+                throttle.consume();
+            }
+        }
+    }
+
+    #[test]
+    fn budget() {
+        // 10 cycles/s at 40 frames/s: one frame in four may be read.
+        let mut throttle = Throttle::new(SpeedLimit::CyclesPerSec(10.0));
+        assert!(throttle.refill(0.025));
+        throttle.consume();
+
+        let ready: Vec<_> = (0..8).map(|_| {
+            let ready = throttle.refill(0.025);
+            if ready { throttle.consume(); }
+            ready
+        }).collect();
+        assert_eq!(ready, vec![false, false, false, true, false, false, false, true]);
+    }
+
+    #[test]
+    fn budget_does_not_accumulate() {
+        let mut throttle = Throttle::new(SpeedLimit::CyclesPerSec(10.0));
+        throttle.consume();
+
+        // A long pause allows a single frame, not a burst:
+        assert!(throttle.refill(5.0));
+        throttle.consume();
+        assert!(!throttle.refill(0.0));
+
+        // Unread frames don't consume the budget:
+        assert!(throttle.refill(0.1));
+        assert!(throttle.refill(0.0));
+    }
+
+    #[test]
+    fn set_limit_resets_budget() {
+        let mut throttle = Throttle::new(SpeedLimit::CyclesPerSec(1.0));
+        throttle.consume();
+        assert!(!throttle.refill(0.5));
+
+        throttle.set_limit(SpeedLimit::CyclesPerSec(2.0));
+        assert_eq!(throttle.limit(), SpeedLimit::CyclesPerSec(2.0));
+        assert!(throttle.refill(0.0));
+    }
+}
@@ -0,0 +1,207 @@
+//! Per-slice and per-layer activity statistics and state histograms.
+
+use glium::Surface;
+use window::{Overlay, HexGridBuffer, TractLayout};
+
+/// States at or above this are counted by `SliceStats::above` by default.
+pub const DEFAULT_STATS_THRESHOLD: u8 = 128;
+
+
+/// Activity statistics of a slice (or several) for a single frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SliceStats {
+    /// Number of axons.
+    pub count: usize,
+    /// Number of axons with a non-zero state.
+    pub active: usize,
+    /// Sum of all states.
+    pub sum: u64,
+    pub max: u8,
+    /// Number of axons with a state at or above the threshold.
+    pub above: usize,
+}
+
+impl SliceStats {
+    pub fn from_states(states: &[u8], threshold: u8) -> SliceStats {
+        let mut stats = SliceStats { count: states.len(), .. SliceStats::default() };
+
+        for &state in states {
+            if state > 0 { stats.active += 1; }
+            if state >= threshold { stats.above += 1; }
+            stats.sum += state as u64;
+            stats.max = stats.max.max(state);
+        }
+        stats
+    }
+
+    /// Combines the statistics of two sets of axons.
+    pub fn merge(&self, other: &SliceStats) -> SliceStats {
+        SliceStats {
+            count: self.count + other.count,
+            active: self.active + other.active,
+            sum: self.sum + other.sum,
+            max: self.max.max(other.max),
+            above: self.above + other.above,
+        }
+    }
+
+    /// The fraction of active axons (the sparsity of an SDR).
+    pub fn active_fraction(&self) -> f32 {
+        if self.count == 0 { 0.0 } else { self.active as f32 / self.count as f32 }
+    }
+
+    pub fn mean(&self) -> f32 {
+        if self.count == 0 { 0.0 } else { self.sum as f32 / self.count as f32 }
+    }
+}
+
+
+/// Computes the statistics of each slice within a frame of states into
+/// `out`, replacing its contents.
+pub fn slice_stats(layout: &TractLayout, states: &[u8], threshold: u8,
+        out: &mut Vec<SliceStats>)
+{
+    out.clear();
+    out.extend(layout.slc_id_range().map(|slc_id| {
+        let axn_id_range = layout.axn_id_range(slc_id..(slc_id + 1));
+        match states.get(axn_id_range) {
+            Some(slc_states) => SliceStats::from_states(slc_states, threshold),
+            None => SliceStats::default(),
+        }
+    }));
+}
+
+
+/// Combines the statistics of the slices of each layer of the most recent
+/// frame, in the order of `TractLayout::layers`.
+pub fn layer_stats(buffer: &HexGridBuffer) -> Vec<(String, SliceStats)> {
+    let slc_stats = buffer.slc_stats();
+    buffer.layout().layers().into_iter().map(|(tags, slc_range)| {
+        let stats = slc_stats.get(slc_range).map(|s| {
+            s.iter().fold(SliceStats::default(), |acc, s| acc.merge(s))
+        }).unwrap_or_default();
+        (tags.to_owned(), stats)
+    }).collect()
+}
+
+
+/// Counts the states of each slice within a frame of states into `out`, one
+/// bin per possible state, reusing its bins.
+pub fn slice_histograms(layout: &TractLayout, states: &[u8], out: &mut Vec<Vec<u32>>) {
+    out.resize(layout.slc_id_range().len(), Vec::new());
+
+    for (slc_id, bins) in layout.slc_id_range().zip(out.iter_mut()) {
+        bins.clear();
+        bins.resize(256, 0);
+        let axn_id_range = layout.axn_id_range(slc_id..(slc_id + 1));
+        if let Some(slc_states) = states.get(axn_id_range) {
+            for &state in slc_states {
+                bins[state as usize] += 1;
+            }
+        }
+    }
+}
+
+
+/// A table of the statistics of each layer of the most recent frame.
+pub struct StatsTable {
+    shown: bool,
+}
+
+impl StatsTable {
+    pub fn new() -> StatsTable {
+        StatsTable { shown: true }
+    }
+
+    pub fn toggle_shown(&mut self) {
+        self.shown = !self.shown;
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.shown
+    }
+
+    /// Returns the rows of the table, starting with the header.
+    pub fn rows(buffer: &HexGridBuffer) -> Vec<Vec<String>> {
+        let mut rows = vec![vec!["Layer".to_owned(), "Active".to_owned(), "Mean".to_owned(),
+            "Max".to_owned(), format!(">= {}", buffer.stats_threshold())]];
+
+        for (tags, stats) in layer_stats(buffer).into_iter().rev() {
+            rows.push(vec![
+                tags,
+                format!("{:.2}%", stats.active_fraction() * 100.0),
+                format!("{:.1}", stats.mean()),
+                stats.max.to_string(),
+                stats.above.to_string(),
+            ]);
+        }
+        rows
+    }
+
+    /// Draws the table with its top left corner at `pos`.
+    pub fn draw<S: Surface>(&self, overlay: &Overlay, target: &mut S, pos: (f32, f32),
+            buffer: &HexGridBuffer)
+    {
+        if !self.shown { return; }
+        overlay.draw_table(target, pos, &StatsTable::rows(buffer));
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use window::{TractLayout, SliceLayout};
+    use super::{SliceStats, slice_stats, slice_histograms};
+
+    #[test]
+    fn from_states() {
+        let stats = SliceStats::from_states(&[0, 3, 128, 255, 0, 127], 128);
+        assert_eq!(stats, SliceStats { count: 6, active: 4, sum: 513, max: 255, above: 2 });
+        assert_eq!(stats.active_fraction(), 4.0 / 6.0);
+        assert_eq!(stats.mean(), 513.0 / 6.0);
+
+        // A zero threshold counts inactive axons too:
+        assert_eq!(SliceStats::from_states(&[0, 1], 0).above, 2);
+    }
+
+    #[test]
+    fn empty() {
+        let stats = SliceStats::from_states(&[], 128);
+        assert_eq!(stats, SliceStats::default());
+        assert_eq!(stats.active_fraction(), 0.0);
+        assert_eq!(stats.mean(), 0.0);
+    }
+
+    #[test]
+    fn merge() {
+        let a = SliceStats::from_states(&[0, 200, 10], 100);
+        let b = SliceStats::from_states(&[255, 0], 100);
+        let merged = a.merge(&b);
+        assert_eq!(merged, SliceStats::from_states(&[0, 200, 10, 255, 0], 100));
+        assert_eq!(merged, b.merge(&a));
+        assert_eq!(a.merge(&SliceStats::default()), a);
+    }
+
+    #[test]
+    fn slices() {
+        let layout = TractLayout::new(vec![
+            SliceLayout { dims: (1, 2), axn_id_range: 0..2, tags: String::new() },
+            SliceLayout { dims: (1, 3), axn_id_range: 2..5, tags: String::new() },
+        ]);
+        let states = [0, 9, 200, 200, 0];
+
+        // Stale contents are replaced:
+        let mut stats = vec![SliceStats::default(); 3];
+        slice_stats(&layout, &states, 100, &mut stats);
+        assert_eq!(stats, vec![SliceStats::from_states(&[0, 9], 100),
+            SliceStats::from_states(&[200, 200, 0], 100)]);
+
+        let mut histograms = vec![vec![7; 256]];
+        slice_histograms(&layout, &states, &mut histograms);
+        assert_eq!(histograms.len(), 2);
+        assert_eq!((histograms[0][0], histograms[0][9], histograms[0].iter().sum::<u32>()),
+            (1, 1, 2));
+        assert_eq!((histograms[1][0], histograms[1][200], histograms[1].iter().sum::<u32>()),
+            (1, 2, 3));
+    }
+}
@@ -0,0 +1,93 @@
+//! The arrangement of slices within an area's axon tract.
+
+use std::ops::Range;
+use bismit::map::SliceTractMap;
+
+
+/// Dimensions, axon indexes, and layer tags of a single slice.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SliceLayout {
+    /// (v, u) grid dimensions.
+    pub dims: (u32, u32),
+    pub axn_id_range: Range<usize>,
+    /// Description of the layer tags of the slice.
+    pub tags: String,
+}
+
+
+/// The slices of an area's axon tract, indexed by slice id.
+///
+/// Carries the same information as a `SliceTractMap` (as much as is needed
+/// for rendering) but can be saved to and loaded from a tract stream file,
+/// allowing replays without a `Cortex`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TractLayout {
+    slcs: Vec<SliceLayout>,
+}
+
+impl TractLayout {
+    pub fn new(slcs: Vec<SliceLayout>) -> TractLayout {
+        TractLayout { slcs: slcs }
+    }
+
+    pub fn from_tract_map(tract_map: &SliceTractMap) -> TractLayout {
+        // Tags are listed from the last slice to the first:
+        let tags = tract_map.tags_reversed();
+
+        let slcs = tract_map.slc_id_range().map(|slc_id| {
+            SliceLayout {
+                dims: tract_map.slc_dims(slc_id as u8),
+                axn_id_range: tract_map.axn_id_range(slc_id..(slc_id + 1)),
+                tags: tags.get(tags.len().wrapping_sub(1 + slc_id))
+                    .map(|t| format!("{:?}", t)).unwrap_or_default(),
+            }
+        }).collect();
+
+        TractLayout { slcs: slcs }
+    }
+
+    pub fn slices(&self) -> &[SliceLayout] {
+        &self.slcs
+    }
+
+    pub fn slc_id_range(&self) -> Range<usize> {
+        0..self.slcs.len()
+    }
+
+    pub fn slc_dims(&self, slc_id: usize) -> (u32, u32) {
+        self.slcs[slc_id].dims
+    }
+
+    /// Returns the range of axon indexes spanned by a range of slices.
+    pub fn axn_id_range(&self, slc_range: Range<usize>) -> Range<usize> {
+        if slc_range.start >= slc_range.end { return 0..0; }
+        self.slcs[slc_range.start].axn_id_range.start..self.slcs[slc_range.end - 1].axn_id_range.end
+    }
+
+    /// Returns the total number of axons.
+    pub fn axn_count(&self) -> usize {
+        self.slcs.last().map(|s| s.axn_id_range.end).unwrap_or(0)
+    }
+
+    pub fn slc_tags(&self, slc_id: usize) -> Option<&str> {
+        self.slcs.get(slc_id).map(|s| s.tags.as_str())
+    }
+
+    /// Groups consecutive slices sharing the same tags (generally one group
+    /// per layer), returning the tags and slice range of each group.
+    pub fn layers(&self) -> Vec<(&str, Range<usize>)> {
+        let mut layers: Vec<(&str, Range<usize>)> = Vec::new();
+
+        for (slc_id, slc) in self.slcs.iter().enumerate() {
+            if let Some(last) = layers.last_mut() {
+                if last.0 == slc.tags {
+                    last.1.end = slc_id + 1;
+                    continue;
+                }
+            }
+            layers.push((slc.tags.as_str(), slc_id..(slc_id + 1)));
+        }
+
+        layers
+    }
+}
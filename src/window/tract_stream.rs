@@ -0,0 +1,520 @@
+//! Tract stream files: raw axon states, frame by frame, along with the
+//! layout of the area they came from.
+//!
+//! All integers are little-endian `u32`s. Layout:
+//!
+//! * Header: `b"VIBITRS\0"`, version, area name, slice count, then for each
+//!   slice: v size, u size, first axon index, axon count, tags.
+//! * Frames (until the end of the file): cycle number, encoding, payload
+//!   length, payload.
+//!
+//! Strings are stored as a length followed by UTF-8 bytes (at most
+//! `MAX_STR_LEN`). Slices start at axon 0 and follow one another without gaps,
+//! and there are at most 256 of them (slice ids are `u8`s). Frame payloads are
+//! either raw states or, if smaller (usually, since activity is sparse), runs
+//! of zeros encoded as a zero byte followed by the run length (1 - 255).
+
+use std::fs::File;
+use std::io::{self, Read, Write, Seek, SeekFrom, BufReader, BufWriter};
+use std::path::Path;
+use window::{TractLayout, SliceLayout};
+
+const MAGIC: &'static [u8; 8] = b"VIBITRS\0";
+const VERSION: u32 = 1;
+
+const MAX_STR_LEN: u32 = 4096;
+const MAX_SLC_COUNT: u32 = 256;
+
+const ENCODING_RAW: u32 = 0;
+const ENCODING_ZERO_RUNS: u32 = 1;
+
+
+fn write_u32<W: Write>(writer: &mut W, val: u32) -> io::Result<()> {
+    writer.write_all(&[val as u8, (val >> 8) as u8, (val >> 16) as u8, (val >> 24) as u8])
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok((bytes[0] as u32) | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16 |
+        (bytes[3] as u32) << 24)
+}
+
+fn write_str<W: Write>(writer: &mut W, s: &str) -> io::Result<()> {
+    if s.len() > MAX_STR_LEN as usize { return Err(invalid_data("String too long.")); }
+    write_u32(writer, s.len() as u32)?;
+    writer.write_all(s.as_bytes())
+}
+
+fn read_str<R: Read>(reader: &mut R) -> io::Result<String> {
+    let len = read_u32(reader)?;
+    if len > MAX_STR_LEN { return Err(invalid_data("String too long.")); }
+    let mut bytes = vec![0u8; len as usize];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+
+/// Encodes runs of zeros (see module docs).
+fn encode_zero_runs(states: &[u8], out: &mut Vec<u8>) {
+    out.clear();
+    let mut run = 0u8;
+
+    for &state in states {
+        if state == 0 {
+            if run == 255 {
+                out.extend_from_slice(&[0, run]);
+                run = 0;
+            }
+            run += 1;
+        } else {
+            if run > 0 {
+                out.extend_from_slice(&[0, run]);
+                run = 0;
+            }
+            out.push(state);
+        }
+    }
+
+    if run > 0 { out.extend_from_slice(&[0, run]); }
+}
+
+fn decode_zero_runs(payload: &[u8], out: &mut Vec<u8>) -> io::Result<()> {
+    out.clear();
+    let mut bytes = payload.iter();
+
+    while let Some(&byte) = bytes.next() {
+        if byte == 0 {
+            let run = *bytes.next().ok_or_else(|| invalid_data("Truncated zero run."))?;
+            out.extend(::std::iter::repeat(0u8).take(run as usize));
+        } else {
+            out.push(byte);
+        }
+    }
+    Ok(())
+}
+
+
+/// Writes a tract stream file.
+pub struct TractWriter {
+    writer: BufWriter<File>,
+    axn_count: usize,
+    frame_count: u32,
+    encoded: Vec<u8>,
+}
+
+impl TractWriter {
+    /// Creates a new file and writes the header.
+    pub fn create<P: AsRef<Path>>(path: P, area_name: &str, layout: &TractLayout)
+            -> io::Result<TractWriter>
+    {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        writer.write_all(MAGIC)?;
+        write_u32(&mut writer, VERSION)?;
+        write_str(&mut writer, area_name)?;
+        write_u32(&mut writer, layout.slices().len() as u32)?;
+
+        for slc in layout.slices() {
+            write_u32(&mut writer, slc.dims.0)?;
+            write_u32(&mut writer, slc.dims.1)?;
+            write_u32(&mut writer, slc.axn_id_range.start as u32)?;
+            write_u32(&mut writer, slc.axn_id_range.len() as u32)?;
+            write_str(&mut writer, &slc.tags)?;
+        }
+
+        Ok(TractWriter {
+            writer: writer,
+            axn_count: layout.axn_count(),
+            frame_count: 0,
+            encoded: Vec::new(),
+        })
+    }
+
+    /// Appends a frame of axon states.
+    pub fn write_frame(&mut self, cycle: u32, states: &[u8]) -> io::Result<()> {
+        if states.len() != self.axn_count {
+            return Err(invalid_data("Frame length does not match the tract layout."));
+        }
+
+        encode_zero_runs(states, &mut self.encoded);
+
+        let (encoding, payload) = if self.encoded.len() < states.len() {
+            (ENCODING_ZERO_RUNS, &self.encoded[..])
+        } else {
+            (ENCODING_RAW, states)
+        };
+
+        write_u32(&mut self.writer, cycle)?;
+        write_u32(&mut self.writer, encoding)?;
+        write_u32(&mut self.writer, payload.len() as u32)?;
+        self.writer.write_all(payload)?;
+
+        self.frame_count += 1;
+        Ok(())
+    }
+
+    pub fn frame_count(&self) -> u32 {
+        self.frame_count
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+
+/// The location of a frame within a tract stream file.
+#[derive(Clone, Copy, Debug)]
+struct FrameIndex {
+    cycle: u32,
+    encoding: u32,
+    offset: u64,
+    len: usize,
+}
+
+
+/// Reads frames from a tract stream file in any order.
+pub struct TractReader {
+    reader: BufReader<File>,
+    area_name: String,
+    layout: TractLayout,
+    frames: Vec<FrameIndex>,
+    payload: Vec<u8>,
+}
+
+impl TractReader {
+    /// Opens a file, reading the header and indexing each frame.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<TractReader> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC { return Err(invalid_data("Not a tract stream file.")); }
+        if read_u32(&mut reader)? != VERSION {
+            return Err(invalid_data("Unsupported tract stream version."));
+        }
+
+        let area_name = read_str(&mut reader)?;
+        let slc_count = read_u32(&mut reader)?;
+        if slc_count > MAX_SLC_COUNT { return Err(invalid_data("Too many slices.")); }
+        let mut slcs = Vec::with_capacity(slc_count as usize);
+        let mut axn_id_end = 0usize;
+
+        for _ in 0..slc_count {
+            let v_size = read_u32(&mut reader)?;
+            let u_size = read_u32(&mut reader)?;
+            let axn_id_start = read_u32(&mut reader)? as usize;
+            let axn_count = read_u32(&mut reader)? as usize;
+            let tags = read_str(&mut reader)?;
+
+            if axn_id_start != axn_id_end {
+                return Err(invalid_data("Slices are not contiguous from axon 0."));
+            }
+            if v_size as u64 * u_size as u64 != axn_count as u64 {
+                return Err(invalid_data("Slice axon count does not match its dimensions."));
+            }
+            axn_id_end = axn_id_start.checked_add(axn_count)
+                .ok_or_else(|| invalid_data("Slice axon range overflows."))?;
+
+            slcs.push(SliceLayout {
+                dims: (v_size, u_size),
+                axn_id_range: axn_id_start..axn_id_end,
+                tags: tags,
+            });
+        }
+
+        // Index frames, stopping at the end of the file (or at a truncated
+        // frame left by an interrupted recording):
+        let mut frames = Vec::new();
+        let file_len = reader.get_ref().metadata()?.len();
+        let mut offset = reader.seek(SeekFrom::Current(0))?;
+
+        while offset + 12 <= file_len {
+            let cycle = read_u32(&mut reader)?;
+            let encoding = read_u32(&mut reader)?;
+            let len = read_u32(&mut reader)? as u64;
+            if offset + 12 + len > file_len { break; }
+
+            frames.push(FrameIndex { cycle: cycle, encoding: encoding, offset: offset + 12,
+                len: len as usize });
+            offset = reader.seek(SeekFrom::Current(len as i64))?;
+        }
+
+        Ok(TractReader {
+            reader: reader,
+            area_name: area_name,
+            layout: TractLayout::new(slcs),
+            frames: frames,
+            payload: Vec::new(),
+        })
+    }
+
+    pub fn area_name(&self) -> &str {
+        &self.area_name
+    }
+
+    pub fn layout(&self) -> &TractLayout {
+        &self.layout
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Returns the cycle number of a frame.
+    pub fn frame_cycle(&self, frame_idx: usize) -> Option<u32> {
+        self.frames.get(frame_idx).map(|f| f.cycle)
+    }
+
+    /// Reads the axon states of a frame into `states`.
+    pub fn read_frame(&mut self, frame_idx: usize, states: &mut Vec<u8>) -> io::Result<()> {
+        let frame = *self.frames.get(frame_idx)
+            .ok_or_else(|| invalid_data("Frame index out of range."))?;
+
+        self.payload.resize(frame.len, 0);
+        self.reader.seek(SeekFrom::Start(frame.offset))?;
+        self.reader.read_exact(&mut self.payload)?;
+
+        match frame.encoding {
+            ENCODING_RAW => {
+                states.clear();
+                states.extend_from_slice(&self.payload);
+            },
+            ENCODING_ZERO_RUNS => decode_zero_runs(&self.payload, states)?,
+            _ => return Err(invalid_data("Unknown frame encoding.")),
+        }
+
+        if states.len() != self.layout.axn_count() {
+            return Err(invalid_data("Frame length does not match the tract layout."));
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, File, OpenOptions};
+    use std::io::{self, Write};
+    use std::path::{Path, PathBuf};
+    use std::process;
+    use window::{TractLayout, SliceLayout};
+    use super::*;
+
+    /// A file within the temporary directory, removed when dropped.
+    struct TempPath(PathBuf);
+
+    impl AsRef<Path> for TempPath {
+        fn as_ref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    /// Returns a path within the temporary directory unique to this process
+    /// and `name`.
+    fn temp_path(name: &str) -> TempPath {
+        TempPath(env::temp_dir().join(format!("vibi_tract_stream_{}_{}.vtr", process::id(),
+            name)))
+    }
+
+    /// Two 2x3 slices (12 axons).
+    fn layout() -> TractLayout {
+        TractLayout::new(vec![
+            SliceLayout { dims: (2, 3), axn_id_range: 0..6, tags: "aff_in".to_owned() },
+            SliceLayout { dims: (2, 3), axn_id_range: 6..12, tags: "iii | ptal".to_owned() },
+        ])
+    }
+
+    fn frames() -> Vec<(u32, Vec<u8>)> {
+        vec![
+            (10, vec![0, 0, 0, 7, 0, 0, 0, 0, 0, 0, 255, 0]),
+            (20, vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]),
+            (30, vec![0; 12]),
+        ]
+    }
+
+    fn write_file(name: &str) -> TempPath {
+        let path = temp_path(name);
+        let mut writer = TractWriter::create(&path, "v1", &layout()).unwrap();
+        for (cycle, states) in frames() {
+            writer.write_frame(cycle, &states).unwrap();
+        }
+        assert_eq!(writer.frame_count(), 3);
+        writer.flush().unwrap();
+        path
+    }
+
+    fn round_trip(states: &[u8]) -> Vec<u8> {
+        let (mut encoded, mut decoded) = (Vec::new(), Vec::new());
+        encode_zero_runs(states, &mut encoded);
+        decode_zero_runs(&encoded, &mut decoded).unwrap();
+        decoded
+    }
+
+    #[test]
+    fn zero_runs() {
+        let mut states = vec![0u8; 600];
+        states[0] = 9;
+        states[300] = 1;
+        states.extend_from_slice(&[3, 0, 4]);
+        assert_eq!(round_trip(&states), states);
+
+        for &len in &[0, 1, 255, 256, 511, 1000] {
+            assert_eq!(round_trip(&vec![0; len]), vec![0; len]);
+        }
+
+        let mut encoded = Vec::new();
+        encode_zero_runs(&[0; 256], &mut encoded);
+        assert_eq!(encoded, vec![0, 255, 0, 1]);
+        encode_zero_runs(&[5, 0, 0, 6], &mut encoded);
+        assert_eq!(encoded, vec![5, 0, 2, 6]);
+
+        assert!(decode_zero_runs(&[5, 0], &mut encoded).is_err());
+    }
+
+    #[test]
+    fn encoding_choice() {
+        let path = write_file("encoding_choice");
+        let reader = TractReader::open(&path).unwrap();
+        let encodings: Vec<_> = reader.frames.iter().map(|f| f.encoding).collect();
+        assert_eq!(encodings, vec![ENCODING_ZERO_RUNS, ENCODING_RAW, ENCODING_ZERO_RUNS]);
+        assert_eq!(reader.frames[1].len, 12);
+        assert_eq!(reader.frames[2].len, 2);
+
+        // Run encoding must be strictly smaller to be used:
+        let path = temp_path("encoding_choice_equal");
+        let layout = TractLayout::new(vec![SliceLayout { dims: (1, 3), axn_id_range: 0..3,
+            tags: String::new() }]);
+        let mut writer = TractWriter::create(&path, "v1", &layout).unwrap();
+        writer.write_frame(0, &[0, 1, 2]).unwrap();
+        writer.flush().unwrap();
+        assert_eq!(TractReader::open(&path).unwrap().frames[0].encoding, ENCODING_RAW);
+    }
+
+    #[test]
+    fn write_read() {
+        let path = write_file("write_read");
+        let mut reader = TractReader::open(&path).unwrap();
+        assert_eq!(reader.area_name(), "v1");
+        assert_eq!(reader.layout(), &layout());
+        assert_eq!(reader.layout().slc_tags(1), Some("iii | ptal"));
+        assert_eq!(reader.frame_count(), 3);
+
+        let mut states = Vec::new();
+        for (idx, (cycle, frame)) in frames().into_iter().enumerate() {
+            assert_eq!(reader.frame_cycle(idx), Some(cycle));
+            reader.read_frame(idx, &mut states).unwrap();
+            assert_eq!(states, frame);
+        }
+    }
+
+    #[test]
+    fn write_wrong_length() {
+        let path = temp_path("wrong_length");
+        let mut writer = TractWriter::create(&path, "v1", &layout()).unwrap();
+        assert!(writer.write_frame(0, &[0; 11]).is_err());
+        assert_eq!(writer.frame_count(), 0);
+    }
+
+    #[test]
+    fn seek() {
+        let path = write_file("seek");
+        let mut reader = TractReader::open(&path).unwrap();
+        let frames = frames();
+        let mut states = Vec::new();
+
+        for &idx in &[2, 0, 1, 1, 2] {
+            reader.read_frame(idx, &mut states).unwrap();
+            assert_eq!(states, frames[idx].1);
+        }
+        assert!(reader.read_frame(3, &mut states).is_err());
+        assert_eq!(reader.frame_cycle(3), None);
+    }
+
+    #[test]
+    fn truncated_last_frame() {
+        let path = write_file("truncated");
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        let len = file.metadata().unwrap().len();
+
+        // Part way through the last payload, then part way through its header:
+        for &cut in &[1, 13] {
+            file.set_len(len - cut).unwrap();
+            let mut reader = TractReader::open(&path).unwrap();
+            assert_eq!(reader.frame_count(), 2);
+
+            let mut states = Vec::new();
+            reader.read_frame(1, &mut states).unwrap();
+            assert_eq!(states, frames()[1].1);
+        }
+    }
+
+    fn open_err(name: &str, header: &[u8]) -> io::ErrorKind {
+        let path = temp_path(name);
+        File::create(&path).unwrap().write_all(header).unwrap();
+        match TractReader::open(&path) {
+            Err(err) => err.kind(),
+            Ok(_) => panic!("'{}' unexpectedly opened", name),
+        }
+    }
+
+    /// A header for area "v1" with `slc_count` slices, of which those given
+    /// as (v size, u size, first axon index, axon count) are written out with
+    /// `tags_len` bytes of tags each.
+    fn header(slc_count: u32, slcs: &[(u32, u32, u32, u32)], tags_len: u32) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        write_u32(&mut bytes, VERSION).unwrap();
+        write_str(&mut bytes, "v1").unwrap();
+        write_u32(&mut bytes, slc_count).unwrap();
+        for &(v_size, u_size, axn_id_start, axn_count) in slcs {
+            for &val in &[v_size, u_size, axn_id_start, axn_count, tags_len] {
+                write_u32(&mut bytes, val).unwrap();
+            }
+            bytes.extend(::std::iter::repeat(b'a').take(tags_len.min(16) as usize));
+        }
+        bytes
+    }
+
+    #[test]
+    fn bad_header() {
+        assert_eq!(open_err("bad_magic", b"VIBITRX\0\x01\0\0\0"), io::ErrorKind::InvalidData);
+        assert_eq!(open_err("bad_version", b"VIBITRS\0\x02\0\0\0"), io::ErrorKind::InvalidData);
+        assert_eq!(open_err("short", b"VIBI"), io::ErrorKind::UnexpectedEof);
+
+        // Well formed, for reference:
+        let path = temp_path("good_header");
+        File::create(&path).unwrap()
+            .write_all(&header(2, &[(2, 3, 0, 6), (1, 4, 6, 4)], 3)).unwrap();
+        assert_eq!(TractReader::open(&path).unwrap().layout().axn_count(), 10);
+
+        let mut long_name = MAGIC.to_vec();
+        write_u32(&mut long_name, VERSION).unwrap();
+        write_u32(&mut long_name, u32::max_value()).unwrap();
+
+        let bad = [
+            ("long_name", long_name),
+            ("long_tags", header(1, &[(2, 3, 0, 6)], MAX_STR_LEN + 1)),
+            ("many_slcs", header(MAX_SLC_COUNT + 1, &[], 0)),
+            ("late_start", header(1, &[(2, 3, 1, 6)], 0)),
+            ("gap", header(2, &[(2, 3, 0, 6), (1, 4, 7, 4)], 0)),
+            ("overlap", header(2, &[(2, 3, 0, 6), (1, 4, 5, 4)], 0)),
+            ("wrong_count", header(1, &[(2, 3, 0, 5)], 0)),
+            ("huge_dims", header(1, &[(0x10000, 0x10000, 0, 0)], 0)),
+        ];
+        for &(name, ref bytes) in bad.iter() {
+            assert_eq!(open_err(name, bytes), io::ErrorKind::InvalidData, "{}", name);
+        }
+    }
+}
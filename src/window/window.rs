@@ -1,12 +1,25 @@
-use std::sync::mpsc::{Receiver, Sender, TryRecvError, SendError};
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError, SendError};
 use time::{self, Timespec, Duration};
 // use glium::{self, Surface, DisplayBuild};
-use glium::{self, glutin, Surface};
+use glium::{self, glutin, Surface, Rect};
 // use glium::backend::glutin::Display;
 // use cycle::{CyCmd, CyRes, Status as CyStatus, AreaInfo};
 use bismit::{SamplerKind, SamplerBufferKind};
-use bismit::flywheel::{Command, Request, Response, Status, AreaInfo};
-use window::{HexGrid, StatusText};
+use bismit::flywheel::{Command, Request, Response, Status};
+use bismit::map::LayerAddress;
+use window::{HexGrid, HexGridBuffer, Hud, Overlay, Selection, CellLayerInfo, SynBuffer,
+    Recorder, TractLayout, TractWriter, Replay, AreaDesc, LayerList,
+    Encoding, DisplayMode, StatsTable, PlotPanel,
+    HistogramPanel, KeyBindings, Action, SpeedLimit, Throttle};
+use window::color_map;
+use window::history::DEFAULT_TRAIL_LEN;
+use window::stats::{self, DEFAULT_STATS_THRESHOLD};
+use window::plot;
+use window::histogram::DEFAULT_BIN_COUNT;
+use window::screenshot;
 use enamel::{ui, Pane, EventRemainder, UiRequest, TextBox, HexButton, ElementState,
     MouseButton, MouseScrollDelta, SetMouseFocus, Event, WindowEvent};
 
@@ -24,8 +37,15 @@ pub enum WindowCtl {
     Event(Event),
     HexGrid(HexGridCtl),
     SetCyIters(u32),
+    SetSpeedLimit(SpeedLimit),
     CyIterate,
+    /// Pauses the current run or resumes a paused one.
+    CyPauseResume,
+    CyStep,
     CyCmd(Command),
+    Screenshot,
+    ToggleRecording,
+    SwitchArea(String),
     Close,
 }
 
@@ -42,6 +62,40 @@ impl Default for WindowCtl {
 }
 
 
+/// Where a window gets its axon states from.
+pub enum Source {
+    /// A flywheel driving a cortex.
+    Flywheel {
+        command_tx: Sender<Command>,
+        request_tx: Sender<Request>,
+        response_rx: Receiver<Response>,
+        /// Every area within the cortex.
+        areas: Vec<AreaDesc>,
+    },
+    /// A tract stream file.
+    Replay(Replay),
+}
+
+
+/// Where the tract receiver from a `Response::Sampler` belongs.
+///
+/// Responses arrive in request order.
+#[derive(Clone, Debug)]
+enum SamplerDest {
+    /// The main hex grid, while it is displaying the named area.
+    HexGrid(String),
+    ExtraView(String),
+    Selection(LayerAddress, SynBuffer),
+}
+
+
+/// An additional area displayed alongside the main one.
+pub struct AreaView<'d> {
+    pub name: String,
+    pub hex_grid: HexGrid<'d>,
+}
+
+
 pub struct WindowStats {
     pub frame_count: usize,
     pub start_time: Timespec,
@@ -94,15 +148,128 @@ impl WindowStats {
 }
 
 
+/// Window creation and startup options.
+#[derive(Clone, Debug)]
+pub struct WindowSettings {
+    /// Initial window dimensions (width, height).
+    pub dims: (u32, u32),
+    /// Start fullscreen on the primary monitor.
+    pub fullscreen: bool,
+    pub vsync: bool,
+    /// Multisampling level (0 to disable).
+    pub multisampling: u16,
+    /// Initial iteration count used when 'Cycle' is pressed.
+    pub iters: u32,
+    /// Initial limit on how fast runs proceed.
+    pub speed_limit: SpeedLimit,
+    /// Number of iterations to run immediately after the window opens.
+    pub autorun_iters: Option<u32>,
+    /// Close the window once the autorun iterations have completed.
+    pub exit_after_run: bool,
+    /// Directory where screenshots and recordings are saved.
+    pub output_dir: PathBuf,
+    /// Number of cycles between recorded frames.
+    pub record_every: u32,
+    /// Start recording as soon as the window opens.
+    pub record_on_start: bool,
+    /// Command (with arguments) to pipe recorded frames to, as PNG data.
+    pub encoder_cmd: Option<String>,
+    /// Start recording the raw tract stream as soon as the window opens.
+    pub record_tract_on_start: bool,
+    /// Additional areas to display alongside the main one.
+    pub extra_areas: Vec<String>,
+    /// Number of frames over which activity trails fade out.
+    pub trail_len: u32,
+    /// States at or above this are counted in layer statistics.
+    pub stats_threshold: u8,
+    /// Number of bins in state histograms (1 - 256).
+    pub histogram_bins: usize,
+    /// Names of the HUD sections initially shown (all if `None`).
+    pub hud_sections: Option<Vec<String>>,
+    /// HUD text scale, applied on top of DPI and window size scaling.
+    pub hud_scale: f32,
+    /// (action, keys) pairs replacing the default key bindings (see
+    /// `KeyBindings::rebind`).
+    pub key_bindings: Vec<(String, String)>,
+}
+
+impl Default for WindowSettings {
+    fn default() -> WindowSettings {
+        WindowSettings {
+            dims: (1600, 900),
+            fullscreen: false,
+            vsync: true,
+            multisampling: 8,
+            iters: 1000000,
+            speed_limit: SpeedLimit::Unlimited,
+            autorun_iters: None,
+            exit_after_run: false,
+            output_dir: PathBuf::from("."),
+            record_every: 100,
+            record_on_start: false,
+            encoder_cmd: None,
+            record_tract_on_start: false,
+            extra_areas: Vec::new(),
+            trail_len: DEFAULT_TRAIL_LEN,
+            stats_threshold: DEFAULT_STATS_THRESHOLD,
+            histogram_bins: DEFAULT_BIN_COUNT,
+            hud_sections: None,
+            hud_scale: 1.0,
+            key_bindings: Vec::new(),
+        }
+    }
+}
+
+
+/// Background color of the window (and of screenshots).
+const CLEAR_COLOR: (f32, f32, f32, f32) = (0.030, 0.050, 0.080, 1.0);
+
+/// Maximum mouse movement (in pixels) between a press and release which is
+/// still considered a click rather than a drag.
+const CLICK_MAX_MOVE: i32 = 3;
+
+/// Vertical distance between area switcher buttons.
+const AREA_BUTTON_SPACING: f32 = 0.18;
+
+/// Camera movement per navigation key press: pan in pixels, zoom in scroll
+/// units, orbit in pixels.
+const KEY_PAN_STEP: i32 = 20;
+const KEY_ZOOM_STEP: f32 = 50.0;
+const KEY_ORBIT_STEP: i32 = 20;
+
+/// Frames skipped by 'Page Up'/'Page Down' during a replay.
+const REPLAY_PAGE_FRAMES: usize = 100;
+
+/// Largest iteration count reachable with the 'more iterations' key.
+const MAX_KEY_ITERS: u32 = 100000000;
+
+
 // [FIXME]: Needs a rename. Anything containing 'Window' is misleading (Pane is the window).
 pub struct Window<'d> {
     pub cycle_status: Status,
     pub cycle_in_progress: bool,
-    pub area_info: AreaInfo,
+    pub area_name: String,
     pub stats: WindowStats,
     pub close_pending: bool,
     pub grid_dims: (u32, u32),
     pub iters_pending: u32,
+    /// Total cycle count at which the current run completes.
+    pub run_target: Option<u32>,
+    /// Iterations remaining in a paused run.
+    pub paused_iters: Option<u32>,
+    /// Total cycle count at which the current single step completes.
+    pub step_target: Option<u32>,
+    /// The flywheel has reported the current step's cycle finished.
+    step_cycled: bool,
+    /// The main sampler has delivered a frame since the current step began.
+    step_frame_received: bool,
+    /// A run has been stopped to pause it and the status reporting where it
+    /// stopped has not yet been received.
+    pause_pending: bool,
+    pub throttle: Throttle,
+    /// A speed limit switching backpressure, applied once the current run
+    /// ends.
+    speed_limit_pending: Option<SpeedLimit>,
     pub command_tx: Sender<Command>,
     pub request_tx: Sender<Request>,
     pub response_rx: Receiver<Response>,
@@ -110,30 +277,71 @@ pub struct Window<'d> {
     pub has_mouse_focus: bool,
     pub mouse_pos: (i32, i32),
     pub dragging: Option<(i32, i32)>,
+    pub orbiting: Option<(i32, i32)>,
+    pub exit_after_run: bool,
+    pub autorun_started: bool,
+    pub areas: Vec<AreaDesc>,
+    pub extra_views: Vec<AreaView<'d>>,
+    pub cell_layers: Vec<CellLayerInfo>,
+    pub selection: Option<Selection>,
+    click_start: Option<(i32, i32)>,
+    pending_samplers: VecDeque<SamplerDest>,
+    pub output_dir: PathBuf,
+    pub screenshot_pending: bool,
+    pub area_switch_pending: Option<String>,
+    pub record_every: u32,
+    pub encoder_cmd: Option<String>,
+    pub recorder: Option<Recorder>,
+    pub tract_writer: Option<TractWriter>,
+    pub replay: Option<Replay>,
+    pub layer_list: LayerList,
+    pub stats_table: StatsTable,
+    pub plot_panel: PlotPanel,
+    pub histogram_panel: HistogramPanel,
+    pub hud: Hud,
+    pub key_bindings: KeyBindings,
+    pub help_shown: bool,
+    pub fullscreen: bool,
+    pub fullscreen_toggle_pending: bool,
 }
 
 impl<'d> Window<'d> {
-    pub fn open(command_tx: Sender<Command>, request_tx: Sender<Request>,
-                response_rx: Receiver<Response>) {
+    pub fn open(source: Source, settings: WindowSettings) {
+        let (command_tx, request_tx, response_rx, areas, replay) = match source {
+            Source::Flywheel { command_tx, request_tx, response_rx, areas } => {
+                (command_tx, request_tx, response_rx, areas, None)
+            },
+            Source::Replay(replay) => {
+                // Replays have no flywheel. These channels are disconnected
+                // and never used:
+                let (command_tx, _) = mpsc::channel();
+                let (request_tx, _) = mpsc::channel();
+                let (_, response_rx) = mpsc::channel();
+                (command_tx, request_tx, response_rx, Vec::new(), Some(replay))
+            },
+        };
 
         // println!("VIBI: 0");
 
-        // Get initial area info:
-        request_tx.send(Request::AreaInfo)
-            .expect("Error requesting current area name");
-        command_tx.send(Command::None).unwrap();
-
-        let area_info;
-        loop {
-            println!("VIBI: Attempting to receive area info...");
-            match response_rx.recv().expect("vibi::Window::open: Current area name reception error") {
-                Response::AreaInfo(info) => {
-                    area_info = *info;
-                    break;
-                },
-                _ => (),
-            };
-        }
+        let (area_name, layout) = match replay {
+            Some(ref replay) => (replay.area_name().to_owned(), replay.layout().clone()),
+            None => {
+                // Get initial area info:
+                request_tx.send(Request::AreaInfo)
+                    .expect("Error requesting current area name");
+                command_tx.send(Command::None).unwrap();
+
+                loop {
+                    println!("VIBI: Attempting to receive area info...");
+                    match response_rx.recv().expect("vibi::Window::open: Current area name reception error") {
+                        Response::AreaInfo(info) => {
+                            break (info.name.clone(), TractLayout::from_tract_map(&info.tract_map));
+                        },
+                        _ => (),
+                    };
+                }
+            },
+        };
 
         println!("VIBI: Creating window...");
 
@@ -149,24 +357,99 @@ impl<'d> Window<'d> {
         //     .build_glium().unwrap();
 
         let mut events_loop = glutin::EventsLoop::new();
+        let fullscreen_monitor = if settings.fullscreen {
+            Some(events_loop.get_primary_monitor())
+        } else {
+            None
+        };
         let window = glutin::WindowBuilder::new()
-            .with_dimensions(1600, 900)
+            .with_dimensions(settings.dims.0, settings.dims.1)
+            .with_fullscreen(fullscreen_monitor)
             .with_title("Vibi".to_string());
         let context = glutin::ContextBuilder::new()
             .with_depth_buffer(24)
-            .with_vsync(true)
-            .with_multisampling(8);
+            .with_vsync(settings.vsync)
+            .with_multisampling(settings.multisampling);
         let display = glium::Display::new(window, context, &events_loop).unwrap();
 
         // Hex grid:
-        let hex_grid = HexGrid::new(&display, area_info.clone());
+        let hex_grid = HexGrid::new(&display, layout);
         let grid_dims = hex_grid.buffer.aff_out_grid_dims();
 
-        // Status text UI element (fps & grid side):
-        let status_text = StatusText::new(&display);
+        let cell_layers = areas.iter().find(|a| a.name == area_name)
+            .map(|a| a.cell_layers.clone()).unwrap_or(Vec::new());
+
+        // Additional areas:
+        let mut extra_views = Vec::with_capacity(settings.extra_areas.len());
+        for name in settings.extra_areas.iter().filter(|&n| n != &area_name) {
+            match areas.iter().find(|a| &a.name == name) {
+                Some(area) => extra_views.push(AreaView {
+                    name: area.name.clone(),
+                    hex_grid: HexGrid::new(&display, area.layout.clone()),
+                }),
+                None => println!("VIBI: Unknown area: '{}'.", name),
+            }
+        }
+
+        // Heads-up display (camera, timing, area info, etc.):
+        let mut hud = Hud::with_defaults();
+        hud.set_scale(settings.hud_scale);
+        if let Some(ref names) = settings.hud_sections {
+            if let Err(name) = hud.show_only(names) {
+                println!("VIBI: Unknown HUD section: '{}'.", name);
+            }
+        }
+
+        // Keyboard shortcuts:
+        let mut key_bindings = KeyBindings::default();
+        for &(ref action, ref keys) in settings.key_bindings.iter() {
+            if let Err(e) = key_bindings.rebind(action, keys) {
+                println!("VIBI: Error rebinding '{}': {}", action, e);
+            }
+        }
+
+        // Screen-space shapes and text (tooltips, etc.):
+        let overlay = Overlay::new(&display);
+
+        let iters_text = format_iters(settings.iters);
+        let speed_text = settings.speed_limit.label();
 
         // Primary user interface elements:
         let mut ui = Pane::new(&display)
+            .element(HexButton::new(ui::BOTTOM_RIGHT, (-0.57, 0.63), 1.8,
+                    "Pause", ui::C_ORANGE)
+                .mouse_event_handler(Box::new(|_, _| {
+                    (UiRequest::None, WindowCtl::CyPauseResume)
+                }))
+            )
+
+            .element(HexButton::new(ui::BOTTOM_RIGHT, (-0.20, 0.63), 1.8,
+                    "Step", ui::C_ORANGE)
+                .mouse_event_handler(Box::new(|_, _| {
+                    (UiRequest::None, WindowCtl::CyStep)
+                }))
+            )
+
+            .element(TextBox::new(ui::BOTTOM_RIGHT, (-0.385, 0.54), 4.45,
+                    "Speed:", ui::C_ORANGE, &speed_text)
+                .keyboard_event_handler(Box::new(|key_state, vk_code, kb_state,
+                    text_string| {
+                        ui::key_into_string(key_state, vk_code, kb_state, text_string);
+
+                        let remainder = match SpeedLimit::parse(text_string) {
+                            Some(limit) => WindowCtl::SetSpeedLimit(limit),
+                            None => WindowCtl::None,
+                        };
+
+                        (UiRequest::None, remainder)
+                    } )
+                )
+                .mouse_event_handler(Box::new(|_, _| {
+                    (UiRequest::KeyboardFocus(true), WindowCtl::None)
+                })
+
+            ))
+
             .element(HexButton::new(ui::BOTTOM_RIGHT, (-0.57, 0.45), 1.8,
                     "View Output", ui::C_ORANGE)
                 .mouse_event_handler(Box::new(|_, _| {
@@ -182,7 +465,7 @@ impl<'d> Window<'d> {
             )
 
             .element(TextBox::new(ui::BOTTOM_RIGHT, (-0.385, 0.35), 4.45,
-                    "Iters:", ui::C_ORANGE, "1m")
+                    "Iters:", ui::C_ORANGE, &iters_text)
                 .keyboard_event_handler(Box::new(|key_state, vk_code, kb_state,
                     text_string| {
                         ui::key_into_string(key_state, vk_code, kb_state, text_string);
@@ -218,24 +501,53 @@ impl<'d> Window<'d> {
                 }))
             )
 
+            .element(HexButton::new(ui::BOTTOM_RIGHT, (-0.57, 0.07), 1.8,
+                    "Screenshot", ui::C_ORANGE)
+                .mouse_event_handler(Box::new(|_, _| {
+                    (UiRequest::None, WindowCtl::Screenshot)
+                }))
+            )
+
             .element(HexButton::new(ui::BOTTOM_RIGHT, (-0.20, 0.07), 1.8,
                     "Exit", ui::C_ORANGE)
                 .mouse_event_handler(Box::new(|_, _| {
                     (UiRequest::None, WindowCtl::Close)
                 }))
-            )
+            );
+
+        // Area switcher (one button per area, stacked from the bottom):
+        if areas.len() > 1 {
+            for (i, area) in areas.iter().enumerate() {
+                let area_name = area.name.clone();
+                ui = ui.element(HexButton::new(ui::BOTTOM_RIGHT,
+                        (-0.94, 0.07 + AREA_BUTTON_SPACING * i as f32), 1.8,
+                        &area.name, ui::C_ORANGE)
+                    .mouse_event_handler(Box::new(move |_, _| {
+                        (UiRequest::None, WindowCtl::SwitchArea(area_name.clone()))
+                    }))
+                );
+            }
+        }
 
-            .init();
+        let mut ui = ui.init();
 
         // Main window data struct:
         let mut window = Window {
             cycle_status: Status::new(),
             cycle_in_progress: false,
-            area_info: area_info.clone(),
+            area_name: area_name,
             stats: WindowStats::new(),
             close_pending: false,
             grid_dims: grid_dims,
-            iters_pending: 1000000,
+            iters_pending: settings.iters,
+            run_target: None,
+            paused_iters: None,
+            step_target: None,
+            step_cycled: false,
+            step_frame_received: false,
+            pause_pending: false,
+            throttle: Throttle::new(settings.speed_limit),
+            speed_limit_pending: None,
             command_tx: command_tx,
             request_tx: request_tx,
             response_rx: response_rx,
@@ -243,6 +555,32 @@ impl<'d> Window<'d> {
             mouse_pos: (0, 0),
             has_mouse_focus: true,
             dragging: None,
+            orbiting: None,
+            exit_after_run: settings.exit_after_run,
+            autorun_started: false,
+            areas: areas,
+            extra_views: extra_views,
+            cell_layers: cell_layers,
+            selection: None,
+            click_start: None,
+            pending_samplers: VecDeque::new(),
+            output_dir: settings.output_dir.clone(),
+            screenshot_pending: false,
+            area_switch_pending: None,
+            record_every: settings.record_every,
+            encoder_cmd: settings.encoder_cmd.clone(),
+            recorder: None,
+            tract_writer: None,
+            replay: replay,
+            layer_list: LayerList::new(),
+            stats_table: StatsTable::new(),
+            plot_panel: PlotPanel::new(plot::DEFAULT_WINDOW_LEN),
+            histogram_panel: HistogramPanel::new(settings.histogram_bins),
+            hud: hud,
+            key_bindings: key_bindings,
+            help_shown: false,
+            fullscreen: settings.fullscreen,
+            fullscreen_toggle_pending: false,
         };
 
         // // Print some stuff:
@@ -258,26 +596,56 @@ impl<'d> Window<'d> {
             }
         }
 
-        // Send initial requests:
-        handle_init_sends(window.request_tx.send(Request::CurrentIter), &mut window);
-        handle_init_sends(window.command_tx.send(Command::None), &mut window);
-        window.recv_cycle_results(true);
+        if window.replay.is_none() {
+            // Send initial requests:
+            handle_init_sends(window.request_tx.send(Request::CurrentIter), &mut window);
+            handle_init_sends(window.command_tx.send(Command::None), &mut window);
+            window.recv_cycle_results(true);
 
-        handle_init_sends(window.request_tx.send(Request::Status), &mut window);
-        handle_init_sends(window.command_tx.send(Command::None), &mut window);
-        window.recv_cycle_results(true);
+            handle_init_sends(window.request_tx.send(Request::Status), &mut window);
+            handle_init_sends(window.command_tx.send(Command::None), &mut window);
+            window.recv_cycle_results(true);
 
-        // AxonSpace -> HexGridBuffer sampler:
-        handle_init_sends(window.request_tx.send(Request::Sampler {
-                area_name: window.area_info.name.clone(),
-                kind: SamplerKind::Axons(None),
-                buffer_kind: SamplerBufferKind::Single,
-                backpressure: false,
-            }), &mut window);
-        handle_init_sends(window.command_tx.send(Command::None), &mut window);
-        window.recv_cycle_results(true);
+            // AxonSpace -> HexGridBuffer sampler:
+            window.pending_samplers.push_back(SamplerDest::HexGrid(window.area_name.clone()));
+            let request = window.hex_grid_sampler_request();
+            handle_init_sends(window.request_tx.send(request), &mut window);
+            handle_init_sends(window.command_tx.send(Command::None), &mut window);
+            window.recv_cycle_results(true);
+
+            // Samplers for additional areas:
+            let extra_names: Vec<_> = window.extra_views.iter().map(|v| v.name.clone()).collect();
+            for name in extra_names {
+                window.pending_samplers.push_back(SamplerDest::ExtraView(name.clone()));
+                handle_init_sends(window.request_tx.send(Request::Sampler {
+                        area_name: name,
+                        kind: SamplerKind::Axons(None),
+                        buffer_kind: SamplerBufferKind::Single,
+                        backpressure: false,
+                    }), &mut window);
+            }
+            handle_init_sends(window.command_tx.send(Command::None), &mut window);
 
-        if window.close_pending { println!("Send error during vibi window init."); }
+            if window.close_pending { println!("Send error during vibi window init."); }
+        }
+
+        for hex_grid in window.hex_grids_mut() {
+            hex_grid.buffer.history_mut().set_trail_len(settings.trail_len);
+            hex_grid.buffer.set_stats_threshold(settings.stats_threshold);
+        }
+
+        if settings.record_on_start { window.toggle_recording(); }
+        if settings.record_tract_on_start { window.toggle_tract_recording(); }
+
+        if window.replay.is_some() {
+            // Nothing to run.
+        } else if let Some(iters) = settings.autorun_iters {
+            println!("VIBI: Autorunning {} iterations...", iters);
+            handle_init_sends(window.command_tx.send(Command::Iterate(iters)), &mut window);
+            window.run_target = Some(window.cycle_status.ttl_cycles() + iters);
+            window.cycle_in_progress = true;
+            window.autorun_started = true;
+        }
 
         //////////////////////////////////////////////////////////////////////////
         ///////////////////// Primary Event & Rendering Loop /////////////////////
@@ -285,7 +653,7 @@ impl<'d> Window<'d> {
         loop {
             // Create draw target and clear color and depth:
             let mut target = display.draw();
-            target.clear_color_and_depth((0.030, 0.050, 0.080, 1.0), 1.0);
+            target.clear_color_and_depth(CLEAR_COLOR, 1.0);
 
             // Get read for new input:
             ui.set_input_stale();
@@ -306,39 +674,210 @@ impl<'d> Window<'d> {
                 // Early exit (avoids a channel panic):
                 if window.close_pending {
                     target.finish().unwrap();
+                    window.finish_recordings();
                     window.command_tx.send(Command::Exit).ok();
                     break;
                 }
             }
 
-            // AxonSpace -> HexGridBuffer sampler:
-            window.hex_grid.buffer.refresh_vertex_buf();
+            // Replace the main sampler only between runs:
+            if !window.run_in_progress() {
+                if let Some(limit) = window.speed_limit_pending.take() {
+                    window.set_speed_limit(limit);
+                }
+            }
+
+            // Switch areas (requires the display to rebuild buffers):
+            if let Some(name) = window.area_switch_pending.take() {
+                window.switch_area(&display, &name);
+            }
+
+            // Enter or leave fullscreen (requires the display):
+            if window.fullscreen_toggle_pending {
+                window.fullscreen_toggle_pending = false;
+                window.fullscreen = !window.fullscreen;
+                let monitor = if window.fullscreen {
+                    Some(events_loop.get_primary_monitor())
+                } else {
+                    None
+                };
+                display.gl_window().set_fullscreen(monitor);
+            }
+
+            // AxonSpace (or replay) -> HexGridBuffer (no faster than the speed
+            // limit allows while running, which holds back a backpressured
+            // flywheel):
+            window.update_stats_usage();
+            let frame_allowed = window.throttle.ready() || !window.cycle_in_progress;
+            if frame_allowed { window.refresh_main_grid(); }
+            window.update_replay();
+
+            for view in window.extra_views.iter_mut() {
+                view.hex_grid.buffer.refresh_vertex_buf();
+            }
+
+            // Selected cell synapse samplers -> highlights:
+            if let Some(ref mut selection) = window.selection {
+                selection.refresh(&mut window.hex_grid.buffer);
+            }
 
             if window.cycle_in_progress {
                 // Check current iterator for next frame:
                 window.request_tx.send(Request::CurrentIter).unwrap();
             }
+            if window.step_target.is_some() && !window.step_cycled {
+                // Check whether the step's cycle has finished:
+                window.request_tx.send(Request::Status).unwrap();
+                window.command_tx.send(Command::None).unwrap();
+            }
 
             // Increment our counters:
             let elapsed_ms = window.stats.elapsed_ms();
             window.stats.incr();
 
-            // Draw hex grid:
-            window.hex_grid.draw(&mut target, elapsed_ms);
+            // Draw hex grids (arranged side by side when viewing several areas):
+            window.arrange_views(target.get_dimensions());
+
+            // Save or record the hex grids alone (without the UI and overlays):
+            let cycle = window.cur_cycle();
+            let record_due = window.recorder.as_ref().map(|r| r.frame_due(cycle)).unwrap_or(false);
+            if window.screenshot_pending || record_due {
+                let image = window.render_grids(&display, target.get_dimensions(), elapsed_ms);
+
+                if window.screenshot_pending {
+                    window.screenshot_pending = false;
+                    let saved = image.as_ref().map_err(|e| e.clone())
+                        .and_then(|image| screenshot::save_png(image, &window.output_dir, cycle));
+                    match saved {
+                        Ok(path) => println!("VIBI: Screenshot saved to '{}'.", path.display()),
+                        Err(e) => println!("VIBI: Screenshot failed: {}", e),
+                    }
+                }
 
-            // Draw status text:
-            status_text.draw(&mut target, &window.cycle_status, &window.stats, window.grid_dims,
-            &window.area_info.name, window.hex_grid.camera_pos()[2], window.hex_grid.top_right_scene,
-            window.hex_grid.cam_pos_raw(), window.hex_grid.tract_map());
+                if record_due {
+                    let recorded = match (&image, window.recorder.as_mut()) {
+                        (&Ok(ref image), Some(recorder)) => recorder.capture(image, cycle),
+                        (&Err(ref e), _) => Err(e.clone()),
+                        (_, None) => Ok(()),
+                    };
+                    if let Err(e) = recorded {
+                        println!("VIBI: Recording failed: {}", e);
+                        window.toggle_recording();
+                    }
+                }
+            }
+
+            window.hex_grid.draw(&mut target, elapsed_ms);
+            for view in window.extra_views.iter_mut() {
+                view.hex_grid.draw(&mut target, elapsed_ms);
+            }
 
             // Draw UI:
             ui.draw(&mut target);
 
+            // Draw HUD:
+            window.hud.set_dpi_factor(display.gl_window().hidpi_factor());
+            let hud_height = window.hud.draw(&overlay, &mut target, (10.0, 10.0), &window).1;
+
+            // Draw layer checklist (below the HUD):
+            let list_top = if hud_height > 0.0 { hud_height + 20.0 } else { 10.0 };
+            window.layer_list.draw(&overlay, &mut target, (10.0, list_top),
+                &window.hex_grid.buffer);
+
+            // Draw layer statistics (next to the layer checklist):
+            let (list_right, list_top) = window.layer_list.top_right();
+            window.stats_table.draw(&overlay, &mut target, (list_right + 10.0, list_top),
+                &window.hex_grid.buffer);
+
+            // Draw area labels:
+            if !window.extra_views.is_empty() {
+                let (_, height) = target.get_dimensions();
+                let labels = Some((&window.area_name, &window.hex_grid)).into_iter()
+                    .chain(window.extra_views.iter().map(|v| (&v.name, &v.hex_grid)));
+                for (name, hex_grid) in labels {
+                    if let Some(vp) = hex_grid.viewport() {
+                        let top = height as f32 - (vp.bottom + vp.height) as f32;
+                        overlay.draw_text_box(&mut target, (vp.left as f32 + 10.0, top + 10.0),
+                            &[name.clone()]);
+                    }
+                }
+            }
+
+            // Draw selected cell info:
+            let mut right_column_top = 10.0;
+            if let Some(ref selection) = window.selection {
+                let lines = selection.summary();
+                let box_size = overlay.text_box_size(&lines);
+                let (width, _) = target.get_dimensions();
+                overlay.draw_text_box(&mut target, (width as f32 - box_size.0 - 10.0, 10.0),
+                    &lines);
+                right_column_top += box_size.1 + 10.0;
+            }
+
+            // Draw plots (below the selected cell info):
+            {
+                let panel_width = window.plot_panel.size().0;
+                let (width, _) = target.get_dimensions();
+                window.plot_panel.draw(&overlay, &mut target,
+                    (width as f32 - panel_width - 10.0, right_column_top));
+            }
+
+            // Draw replay status:
+            let mut legend_bottom = target.get_dimensions().1 as f32 - 10.0;
+            if let Some(ref replay) = window.replay {
+                let lines = replay.summary();
+                let box_height = overlay.text_box_size(&lines).1;
+                overlay.draw_text_box(&mut target, (10.0, legend_bottom - box_height), &lines);
+                legend_bottom -= box_height + 10.0;
+            }
+
+            // Draw color legend (above the replay status):
+            {
+                let buffer = &window.hex_grid.buffer;
+                color_map::draw_legend(&overlay, &mut target, (10.0, legend_bottom),
+                    &buffer.history().describe(buffer.display_mode()),
+                    &window.hex_grid.visible_encodings());
+            }
+
+            // Draw recording indicator:
+            let mut center_column_top = 10.0;
+            if window.recorder.is_some() || window.tract_writer.is_some() {
+                let mut lines = Vec::with_capacity(2);
+                if let Some(ref recorder) = window.recorder {
+                    lines.push(format!("REC [frames: {}]", recorder.frame_count()));
+                }
+                if let Some(ref tract_writer) = window.tract_writer {
+                    lines.push(format!("REC TRACT [frames: {}]", tract_writer.frame_count()));
+                }
+                let box_size = overlay.text_box_size(&lines);
+                let (width, _) = target.get_dimensions();
+                overlay.draw_text_box(&mut target, ((width as f32 - box_size.0) / 2.0, 10.0),
+                    &lines);
+                center_column_top += box_size.1 + 10.0;
+            }
+
+            // Draw state histograms (below the recording indicator):
+            window.histogram_panel.draw(&overlay, &mut target, center_column_top,
+                &window.hex_grid.buffer);
+
+            // Draw hover tooltip:
+            if window.has_mouse_focus && window.dragging.is_none() &&
+                    window.orbiting.is_none()
+            {
+                window.draw_hover_tooltip(&overlay, &mut target);
+            }
+
+            // Draw key binding help (over everything else):
+            if window.help_shown {
+                window.draw_help(&overlay, &mut target);
+            }
+
             // Swap buffers:
             target.finish().unwrap();
 
             // Clean up and exit if necessary:
             if window.close_pending {
+                window.finish_recordings();
                 window.command_tx.send(Command::Exit).ok();
                 break;
             }
@@ -354,19 +893,72 @@ impl<'d> Window<'d> {
             Response::CurrentIter(iter) => self.cycle_status.cur_cycle.0 = iter,
             Response::Status(cysts) => {
                 self.cycle_status = *cysts;
-                self.cycle_in_progress = self.cycle_status.cycling;
+
+                // Close once the autorun batch has finished (unless paused):
+                if self.exit_after_run && self.autorun_started && self.cycle_in_progress &&
+                        !self.cycle_status.cycling && !self.pause_pending
+                {
+                    self.close_pending = true;
+                }
+
+                self.cycle_in_progress = self.cycle_status.cycling && !self.pause_pending;
                 // println!("Cycle Status: cycling: {}", self.cycle_in_progress);
+
+                if self.cycle_status.cycling {
+                    // Keep asking until the run being paused has stopped:
+                    if self.pause_pending { self.request_tx.send(Request::Status).ok(); }
+                } else {
+                    if self.pause_pending {
+                        self.pause_pending = false;
+                        let ttl_cycles = self.cycle_status.ttl_cycles();
+                        let remaining = self.run_target
+                            .map(|target| target.saturating_sub(ttl_cycles)).unwrap_or(0);
+                        if remaining > 0 {
+                            println!("VIBI: Paused ({} iterations remaining).", remaining);
+                            self.paused_iters = Some(remaining);
+                        }
+                    }
+                    self.run_target = None;
+                }
+
+                if let Some(target) = self.step_target {
+                    if !self.cycle_status.cycling && self.cycle_status.ttl_cycles() >= target {
+                        self.step_cycled = true;
+                        self.try_finish_step();
+                    }
+                }
             },
             Response::AreaInfo(info) => {
                 let info = *info;
-                self.area_info = info.clone();
+                self.area_name = info.name.clone();
                 // FIXME: Reimplement handling of switching visible slices.
                 // self.hex_grid.buffer.set_default_slc_ids(info.aff_out_slc_ids.clone());
-                self.hex_grid.buffer.set_tract_map(info.tract_map);
+                self.hex_grid.buffer.set_layout(TractLayout::from_tract_map(&info.tract_map));
             },
             Response::Exiting => self.close_pending = true,
             Response::Sampler(tract_rx) => {
-                self.hex_grid.buffer.set_tract_buffer(tract_rx);
+                match self.pending_samplers.pop_front() {
+                    Some(SamplerDest::Selection(addr, buf)) => {
+                        // Discard samplers for a previously selected layer:
+                        if let Some(ref mut selection) = self.selection {
+                            if selection.layer().addr == addr {
+                                selection.set_receiver(buf, tract_rx);
+                            }
+                        }
+                    },
+                    Some(SamplerDest::ExtraView(name)) => {
+                        if let Some(view) = self.extra_views.iter_mut().find(|v| v.name == name) {
+                            view.hex_grid.buffer.set_tract_buffer(tract_rx);
+                        }
+                    },
+                    Some(SamplerDest::HexGrid(name)) => {
+                        // Discard samplers for a previously displayed area:
+                        if name == self.area_name {
+                            self.hex_grid.buffer.set_tract_buffer(tract_rx);
+                        }
+                    },
+                    None => self.hex_grid.buffer.set_tract_buffer(tract_rx),
+                }
             },
             _ => (),
         }
@@ -374,6 +966,7 @@ impl<'d> Window<'d> {
 
     fn recv_cycle_results(&mut self, block: bool) -> bool {
         let mut any_recvd = false;
+        if self.replay.is_some() { return any_recvd; }
 
         loop {
             if block {
@@ -422,6 +1015,12 @@ impl<'d> Window<'d> {
                         WindowEvent::MouseInput { device_id: _, state, button, modifiers: _ } => {
                             self.handle_mouse_input(state, button)
                         },
+                        WindowEvent::KeyboardInput { device_id: _, input } => {
+                            self.handle_keyboard_input(input)
+                        },
+                        WindowEvent::Resized(width, height) => {
+                            self.handle_resized((width, height))
+                        },
                         WindowEvent::Touch(touch) => println!("Touch recieved: {:?}", touch),
                         WindowEvent::Closed => self.close_pending = true,
                         _ => (),
@@ -430,33 +1029,704 @@ impl<'d> Window<'d> {
                 _ => (),
             },
             WindowCtl::CyCmd(cmd) => {
+                // Replays have no flywheel, 'Stop' pauses:
+                if let Some(ref mut replay) = self.replay {
+                    match cmd {
+                        Command::Stop => replay.pause(),
+                        Command::Exit => self.close_pending = true,
+                        _ => (),
+                    }
+                    return;
+                }
+
                 // If `Stop`/`Exit` is being sent or if any other command is
                 // being sent while the cycle is in progress, set
                 // `cycle_in_progress` false.
                 match cmd.clone() {
-                    Command::Stop | Command::Exit => self.cycle_in_progress = false,
+                    Command::Stop | Command::Exit => {
+                        self.cycle_in_progress = false;
+                        self.autorun_started = false;
+                        self.run_target = None;
+                        self.paused_iters = None;
+                        self.pause_pending = false;
+                        self.step_target = None;
+                    },
                     _ => if self.cycle_in_progress { self.cycle_in_progress = false; },
                 }
 
                 self.command_tx.send(cmd).unwrap();
             },
             WindowCtl::SetCyIters(i) => self.iters_pending = i,
+            WindowCtl::SetSpeedLimit(limit) => self.set_speed_limit(limit),
             WindowCtl::CyIterate => {
-                self.command_tx.send(Command::Iterate(self.iters_pending)).unwrap();
-                self.cycle_in_progress = true;
+                // Replays have no flywheel, 'Cycle' plays:
+                if let Some(ref mut replay) = self.replay {
+                    replay.play();
+                    return;
+                }
+
+                // Starts a fresh run (discarding any paused one):
+                self.paused_iters = None;
+                let iters = self.iters_pending;
+                self.start_run(iters);
             },
+            WindowCtl::CyPauseResume => {
+                if let Some(ref mut replay) = self.replay {
+                    replay.toggle_play();
+                    return;
+                }
+
+                if self.step_target.is_some() { self.finish_step(); }
+                if let Some(remaining) = self.paused_iters.take() {
+                    println!("VIBI: Resuming ({} iterations remaining)...", remaining);
+                    self.start_run(remaining);
+                } else if self.cycle_in_progress && !self.pause_pending {
+                    self.command_tx.send(Command::Stop).unwrap();
+                    self.cycle_in_progress = false;
+                    self.pause_pending = true;
+                    self.request_tx.send(Request::Status).unwrap();
+                    self.command_tx.send(Command::None).unwrap();
+                }
+            },
+            WindowCtl::CyStep => self.step(),
             WindowCtl::HexGrid(cmd) => {
-                match cmd {
-                    HexGridCtl::SlcRangeDefault => self.hex_grid.buffer.use_default_slc_range(),
-                    HexGridCtl::SlcRangeFull => self.hex_grid.buffer.use_full_slc_range(),
+                for hex_grid in self.hex_grids_mut() {
+                    match cmd {
+                        HexGridCtl::SlcRangeDefault => hex_grid.buffer.use_default_slc_range(),
+                        HexGridCtl::SlcRangeFull => hex_grid.buffer.use_full_slc_range(),
+                    }
+                    hex_grid.fit_to_view();
                 }
-                self.hex_grid.update_cam_pos();
             },
+            WindowCtl::Screenshot => self.screenshot_pending = true,
+            WindowCtl::ToggleRecording => self.toggle_recording(),
+            WindowCtl::SwitchArea(name) => self.area_switch_pending = Some(name),
             WindowCtl::Close => self.close_pending = true,
             // _ => (),
         }
     }
 
+    /// Draws a tooltip describing the hexagon under the mouse, if any.
+    fn draw_hover_tooltip<S: Surface>(&self, overlay: &Overlay, target: &mut S) {
+        let hex_grids = Some(&self.hex_grid).into_iter()
+            .chain(self.extra_views.iter().map(|v| &v.hex_grid));
+        let picked = hex_grids.filter_map(|hg| hg.pick(self.mouse_pos).map(|p| (hg, p))).next();
+
+        if let Some((hex_grid, pick)) = picked {
+            let buffer = &hex_grid.buffer;
+            let state = buffer.axn_state(pick.axn_idx)
+                .map(|s| s.to_string()).unwrap_or_else(|| "-".to_owned());
+            let tags = buffer.layout().slc_tags(pick.slc_id).unwrap_or("-");
+
+            let mut lines = vec![
+                format!("Slice: {} [v: {}, u: {}]", pick.slc_id, pick.v_id, pick.u_id),
+                format!("Axon: {}", pick.axn_idx),
+                format!("State: {}", state),
+                format!("Layer: {}", tags),
+            ];
+
+            match buffer.display_mode() {
+                DisplayMode::DutyCycle => {
+                    if let Some(duty_cycle) = buffer.history().duty_cycle(pick.axn_idx) {
+                        lines.push(format!("Duty cycle: {:.1}%", duty_cycle * 100.0));
+                    }
+                },
+                DisplayMode::Difference => {
+                    let state = buffer.axn_state(pick.axn_idx).unwrap_or(0);
+                    if let Some(change) = buffer.history().change(pick.axn_idx, state) {
+                        lines.push(format!("Change: {}", change));
+                    }
+                },
+                _ => (),
+            }
+
+            overlay.draw_tooltip(target, (self.mouse_pos.0 as f32, self.mouse_pos.1 as f32),
+                &lines);
+        }
+    }
+
+    fn handle_keyboard_input(&mut self, input: glutin::KeyboardInput) {
+        if input.state != ElementState::Pressed { return; }
+
+        let action = match input.virtual_keycode {
+            Some(code) => self.key_bindings.action(code, input.modifiers),
+            None => None,
+        };
+        if let Some(action) = action {
+            self.perform_action(action);
+        }
+    }
+
+    /// Performs the action of a keyboard shortcut.
+    fn perform_action(&mut self, action: Action) {
+        if let Some(ref mut replay) = self.replay {
+            match action {
+                Action::RunStop => return replay.toggle_play(),
+                Action::ReplayBack => return replay.seek_by(-1),
+                Action::ReplayForward => return replay.seek_by(1),
+                Action::ReplayPageBack => return replay.seek_by(-(REPLAY_PAGE_FRAMES as isize)),
+                Action::ReplayPageForward => return replay.seek_by(REPLAY_PAGE_FRAMES as isize),
+                Action::ReplayStart => return replay.seek_fraction(0.0),
+                Action::ReplayEnd => return replay.seek_fraction(1.0),
+                Action::ReplaySlower => return replay.scale_speed(0.5),
+                Action::ReplayFaster => return replay.scale_speed(2.0),
+                _ => (),
+            }
+        }
+
+        match action {
+            Action::RunStop => {
+                let ctl = if self.cycle_in_progress {
+                    WindowCtl::CyCmd(Command::Stop)
+                } else {
+                    WindowCtl::CyIterate
+                };
+                self.handle_event_remainder(ctl);
+            },
+            Action::Step => self.handle_event_remainder(WindowCtl::CyStep),
+            Action::PauseResume => self.handle_event_remainder(WindowCtl::CyPauseResume),
+            Action::MoreIters => {
+                let iters = self.iters_pending.saturating_mul(10).min(MAX_KEY_ITERS);
+                self.set_iters_pending(iters);
+            },
+            Action::FewerIters => {
+                let iters = (self.iters_pending / 10).max(1);
+                self.set_iters_pending(iters);
+            },
+            Action::ToggleLayer(idx) => {
+                if LayerList::toggle_layer(idx, &mut self.hex_grid.buffer) {
+                    self.hex_grid.fit_to_view();
+                }
+            },
+            Action::ViewOutput => {
+                self.handle_event_remainder(WindowCtl::HexGrid(HexGridCtl::SlcRangeDefault))
+            },
+            Action::ViewAll => {
+                self.handle_event_remainder(WindowCtl::HexGrid(HexGridCtl::SlcRangeFull))
+            },
+            Action::PanLeft | Action::PanRight | Action::ZoomIn | Action::ZoomOut |
+                Action::OrbitLeft | Action::OrbitRight | Action::TiltUp |
+                Action::TiltDown => self.navigate(action),
+            Action::FitView => for hex_grid in self.hex_grids_mut() { hex_grid.fit_to_view() },
+            Action::ResetView => for hex_grid in self.hex_grids_mut() { hex_grid.reset_view() },
+            Action::Screenshot => self.screenshot_pending = true,
+            Action::ToggleRecording => self.toggle_recording(),
+            Action::ToggleTractRecording => self.toggle_tract_recording(),
+            Action::ToggleLayerList => self.layer_list.toggle_shown(),
+            Action::ToggleStats => self.stats_table.toggle_shown(),
+            Action::TogglePlots => self.plot_panel.toggle_shown(),
+            Action::ToggleHistograms => self.histogram_panel.toggle_shown(),
+            Action::ToggleHud => self.hud.toggle_shown(),
+            Action::ToggleHudSection(idx) => self.hud.toggle_section(idx),
+            Action::ToggleFullscreen => self.fullscreen_toggle_pending = true,
+            Action::NextDisplayMode => {
+                let mode = self.hex_grid.buffer.display_mode().next();
+                for hex_grid in self.hex_grids_mut() { hex_grid.buffer.set_display_mode(mode) }
+            },
+            Action::ResetHistory => {
+                for hex_grid in self.hex_grids_mut() { hex_grid.buffer.reset_history() }
+            },
+            Action::NextColorMap => self.cycle_encodings(|enc| {
+                Encoding::new(enc.color_map.next(), enc.scale)
+            }),
+            Action::NextScale => self.cycle_encodings(|enc| {
+                Encoding::new(enc.color_map, enc.scale.next())
+            }),
+            Action::ToggleHelp => self.help_shown = !self.help_shown,
+            Action::Exit => self.handle_event_remainder(WindowCtl::Close),
+            // Only meaningful during a replay:
+            Action::ReplayBack | Action::ReplayForward | Action::ReplayPageBack |
+                Action::ReplayPageForward | Action::ReplayStart | Action::ReplayEnd |
+                Action::ReplaySlower | Action::ReplayFaster => (),
+        }
+    }
+
+    /// Returns true while the flywheel is (or may still be) cycling on our
+    /// behalf.
+    fn run_in_progress(&self) -> bool {
+        self.cycle_in_progress || self.pause_pending || self.step_target.is_some()
+    }
+
+    /// Sends `iters` iterations to the flywheel as the current run.
+    fn start_run(&mut self, iters: u32) {
+        self.step_target = None;
+        self.command_tx.send(Command::Iterate(iters)).unwrap();
+        self.run_target = Some(self.cycle_status.ttl_cycles() + iters);
+        self.cycle_in_progress = true;
+    }
+
+    /// Moves, zooms, or orbits the camera of every hex grid.
+    fn navigate(&mut self, action: Action) {
+        let (pan, zoom, orbit) = match action {
+            Action::PanLeft => ((KEY_PAN_STEP, 0), 0.0, (0, 0)),
+            Action::PanRight => ((-KEY_PAN_STEP, 0), 0.0, (0, 0)),
+            Action::ZoomIn => ((0, 0), KEY_ZOOM_STEP, (0, 0)),
+            Action::ZoomOut => ((0, 0), -KEY_ZOOM_STEP, (0, 0)),
+            Action::OrbitLeft => ((0, 0), 0.0, (-KEY_ORBIT_STEP, 0)),
+            Action::OrbitRight => ((0, 0), 0.0, (KEY_ORBIT_STEP, 0)),
+            Action::TiltUp => ((0, 0), 0.0, (0, -KEY_ORBIT_STEP)),
+            Action::TiltDown => ((0, 0), 0.0, (0, KEY_ORBIT_STEP)),
+            _ => return,
+        };
+
+        for hex_grid in self.hex_grids_mut() {
+            if pan != (0, 0) { hex_grid.move_camera(pan); }
+            if zoom != 0.0 { hex_grid.zoom_camera(zoom); }
+            if orbit != (0, 0) { hex_grid.orbit_camera(orbit); }
+        }
+    }
+
+    /// Runs exactly one cycle (of the paused run, if any).
+    ///
+    /// The main loop polls the flywheel's status until the cycle has finished
+    /// and waits for the main sampler to deliver its frame (see
+    /// `::try_finish_step`). Further steps are ignored until then, so none is
+    /// skipped.
+    fn step(&mut self) {
+        if self.replay.is_some() || self.cycle_in_progress || self.pause_pending ||
+                self.step_target.is_some()
+        {
+            return;
+        }
+
+        // Display any frame left over from before, so that the next one
+        // received is the step's own:
+        self.refresh_main_grid();
+
+        self.command_tx.send(Command::Iterate(1)).unwrap();
+        self.step_target = Some(self.cycle_status.ttl_cycles() + 1);
+        self.step_cycled = false;
+        self.step_frame_received = false;
+        self.request_tx.send(Request::Status).unwrap();
+        self.command_tx.send(Command::None).unwrap();
+    }
+
+    /// Ends the current step once its cycle has finished and the main
+    /// sampler has delivered the frame it wrote after that cycle.
+    fn try_finish_step(&mut self) {
+        if self.step_target.is_some() && self.step_cycled && self.step_frame_received {
+            self.finish_step();
+        }
+    }
+
+    /// Ends the current step, counting it against the paused run, if any.
+    fn finish_step(&mut self) {
+        self.step_target = None;
+        self.paused_iters = match self.paused_iters {
+            Some(remaining) if remaining > 1 => Some(remaining - 1),
+            _ => None,
+        };
+    }
+
+    /// Sets the iteration count used by the next run.
+    ///
+    /// The 'Iters:' box keeps showing what was last typed into it; the HUD
+    /// shows the current count.
+    fn set_iters_pending(&mut self, iters: u32) {
+        self.iters_pending = iters;
+        println!("VIBI: Iterations per run: {}.", format_iters(iters));
+    }
+
+    /// Draws a table of key bindings in the middle of the window, split into
+    /// two columns.
+    fn draw_help<S: Surface>(&self, overlay: &Overlay, target: &mut S) {
+        let mut rows = self.key_bindings.help_rows();
+        rows.push(vec!["Right mouse drag".to_owned(), "Orbit and tilt".to_owned()]);
+
+        let half = (rows.len() + 1) / 2;
+        let mut table = vec![vec!["Key".to_owned(), "Action".to_owned(), String::new(),
+            "Key".to_owned(), "Action".to_owned()]];
+        for i in 0..half {
+            let mut row = rows[i].clone();
+            row.push(String::new());
+            if let Some(right) = rows.get(i + half) {
+                row.extend(right.iter().cloned());
+            }
+            table.push(row);
+        }
+
+        let (width, height) = target.get_dimensions();
+        let size = overlay.table_size(&table);
+        overlay.draw_table(target, (((width as f32 - size.0) / 2.0).max(0.0),
+            ((height as f32 - size.1) / 2.0).max(0.0)), &table);
+    }
+
+    /// Displays another area in the main hex grid.
+    ///
+    /// A flywheel only provides `AreaInfo` for the area it was created with,
+    /// so the tract layout gathered at startup (see `AreaDesc`) is used to
+    /// rebuild the buffer before sampling the new area.
+    fn switch_area(&mut self, display: &glium::Display, name: &str) {
+        if name == self.area_name || self.replay.is_some() { return; }
+
+        let area = match self.areas.iter().find(|a| a.name == name) {
+            Some(area) => area.clone(),
+            None => {
+                println!("VIBI: Unknown area: '{}'.", name);
+                return;
+            },
+        };
+
+        // Recordings and selections refer to the previous area's layout:
+        if self.tract_writer.is_some() { self.toggle_tract_recording(); }
+        self.selection = None;
+
+        // The step's frame would come from the sampler being replaced:
+        if self.step_target.is_some() { self.finish_step(); }
+        self.cell_layers = area.cell_layers.clone();
+
+        let mut buffer = HexGridBuffer::new(area.layout.clone(), display);
+        if self.hex_grid.buffer.all_slcs_visible() {
+            buffer.use_full_slc_range();
+        }
+        buffer.history_mut().set_trail_len(self.hex_grid.buffer.history().trail_len());
+        buffer.set_display_mode(self.hex_grid.buffer.display_mode());
+        buffer.set_stats_threshold(self.hex_grid.buffer.stats_threshold());
+        self.hex_grid.buffer = buffer;
+        self.hex_grid.fit_to_view();
+        self.grid_dims = self.hex_grid.buffer.aff_out_grid_dims();
+        self.area_name = area.name.clone();
+        self.plot_panel.clear();
+
+        println!("VIBI: Switching to area: '{}'...", self.area_name);
+        self.pending_samplers.push_back(SamplerDest::HexGrid(self.area_name.clone()));
+        self.request_tx.send(self.hex_grid_sampler_request()).unwrap();
+        self.command_tx.send(Command::None).unwrap();
+    }
+
+    /// Returns a request for a sampler of the main area's axon states, with
+    /// backpressure if runs are speed limited.
+    fn hex_grid_sampler_request(&self) -> Request {
+        Request::Sampler {
+            area_name: self.area_name.clone(),
+            kind: SamplerKind::Axons(None),
+            buffer_kind: SamplerBufferKind::Single,
+            backpressure: self.throttle.limit().backpressure(),
+        }
+    }
+
+    /// Changes the speed limit, replacing the main sampler if backpressure
+    /// is to be switched on or off.
+    ///
+    /// The flywheel may be blocked on a backpressured sampler mid-cycle, so
+    /// limits switching backpressure are deferred until the current run (or
+    /// step) ends. Resuming a paused run uses the new limit.
+    fn set_speed_limit(&mut self, limit: SpeedLimit) {
+        let prev_limit = self.throttle.limit();
+        let switches_backpressure = limit.backpressure() != prev_limit.backpressure();
+        if self.replay.is_none() && switches_backpressure && self.run_in_progress() {
+            if self.speed_limit_pending.is_none() {
+                println!("VIBI: Speed limit '{}' applies once the current run ends.",
+                    limit.label());
+            }
+            self.speed_limit_pending = Some(limit);
+            return;
+        }
+
+        self.speed_limit_pending = None;
+        if limit == prev_limit { return; }
+        self.throttle.set_limit(limit);
+
+        if self.replay.is_none() && switches_backpressure {
+            self.pending_samplers.push_back(SamplerDest::HexGrid(self.area_name.clone()));
+            self.request_tx.send(self.hex_grid_sampler_request()).unwrap();
+            self.command_tx.send(Command::None).unwrap();
+        }
+    }
+
+    /// Renders the hex grids offscreen as currently arranged within a window
+    /// of `dims`, without the UI or any overlays.
+    fn render_grids(&mut self, display: &glium::Display, dims: (u32, u32), elapsed_ms: f64)
+            -> Result<screenshot::Image, String>
+    {
+        screenshot::render_offscreen(display, dims, CLEAR_COLOR, |framebuffer| {
+            for hex_grid in self.hex_grids_mut() {
+                hex_grid.draw(&mut *framebuffer, elapsed_ms);
+            }
+        })
+    }
+
+    /// Returns the main hex grid followed by those of any additional areas.
+    fn hex_grids_mut<'s>(&'s mut self) -> Vec<&'s mut HexGrid<'d>> {
+        let mut hex_grids = vec![&mut self.hex_grid];
+        hex_grids.extend(self.extra_views.iter_mut().map(|v| &mut v.hex_grid));
+        hex_grids
+    }
+
+    /// Divides the window into a grid of viewports, one per area (or uses the
+    /// whole window when viewing a single area).
+    fn arrange_views(&mut self, window_dims: (u32, u32)) {
+        let view_count = 1 + self.extra_views.len() as u32;
+        if view_count == 1 {
+            self.hex_grid.set_viewport(None);
+            return;
+        }
+
+        // Prefer more columns than rows (windows are usually wide):
+        let cols = (view_count as f32).sqrt().ceil() as u32;
+        let rows = (view_count + cols - 1) / cols;
+        let (width, height) = (window_dims.0 / cols, window_dims.1 / rows);
+
+        for (i, hex_grid) in self.hex_grids_mut().into_iter().enumerate() {
+            let (col, row) = (i as u32 % cols, i as u32 / cols);
+            hex_grid.set_viewport(Some(Rect {
+                left: col * width,
+                // Rows are counted from the top:
+                bottom: window_dims.1 - (row + 1) * height,
+                width: width,
+                height: height,
+            }));
+        }
+    }
+
+    /// Changes the encoding of the layer under the mouse, or of every layer
+    /// of every view if there is none.
+    fn cycle_encodings(&mut self, change: fn(Encoding) -> Encoding) {
+        if let Some(slc_range) = self.hovered_layer() {
+            let encoding = change(self.hex_grid.encoding(slc_range.start));
+            self.hex_grid.set_encoding(slc_range, encoding);
+            return;
+        }
+
+        for hex_grid in self.hex_grids_mut() {
+            let slc_ranges: Vec<_> = hex_grid.layout().layers().into_iter()
+                .map(|(_, slc_range)| slc_range).collect();
+            for slc_range in slc_ranges {
+                let encoding = change(hex_grid.encoding(slc_range.start));
+                hex_grid.set_encoding(slc_range, encoding);
+            }
+        }
+    }
+
+    /// Returns the slice range of the layer under the mouse in the main view.
+    fn hovered_layer(&self) -> Option<Range<usize>> {
+        let pick = match self.hex_grid.pick(self.mouse_pos) {
+            Some(pick) => pick,
+            None => return None,
+        };
+
+        self.hex_grid.layout().layers().into_iter()
+            .map(|(_, slc_range)| slc_range)
+            .find(|slc_range| slc_range.start <= pick.slc_id && pick.slc_id < slc_range.end)
+    }
+
+    /// Re-arranges views and re-frames cameras for the new window size.
+    ///
+    /// Projections are also rebuilt from the target dimensions each frame;
+    /// this just avoids drawing one frame with stale camera framing.
+    fn handle_resized(&mut self, window_dims: (u32, u32)) {
+        if window_dims.0 == 0 || window_dims.1 == 0 { return; }
+        self.arrange_views(window_dims);
+        for hex_grid in self.hex_grids_mut() {
+            hex_grid.set_window_dims(window_dims);
+        }
+    }
+
+    /// Returns the cycle number of the states currently displayed.
+    fn cur_cycle(&self) -> u32 {
+        match self.replay {
+            Some(ref replay) => replay.cycle(),
+            None => self.cycle_status.ttl_cycles(),
+        }
+    }
+
+    /// Advances replay playback and displays the current frame.
+    fn update_replay(&mut self) {
+        let mut states = Vec::new();
+        let res = match self.replay {
+            Some(ref mut replay) => replay.update(&mut states),
+            None => return,
+        };
+
+        match res {
+            Ok(true) => {
+                self.hex_grid.buffer.set_states(&states);
+                self.record_plot_samples();
+            },
+            Ok(false) => (),
+            Err(e) => {
+                println!("VIBI: Error reading replay frame: {}", e);
+                self.replay.as_mut().unwrap().pause();
+            },
+        }
+    }
+
+    /// Computes slice statistics and histograms only while something showing
+    /// them (the stats table, plots, HUD activity section, or histograms) is.
+    fn update_stats_usage(&mut self) {
+        let stats_shown = self.stats_table.is_shown() || self.plot_panel.is_shown()
+            || self.hud.is_section_shown("stats");
+        self.hex_grid.buffer.set_stats_enabled(stats_shown);
+        self.hex_grid.buffer.set_histograms_enabled(self.histogram_panel.is_shown());
+    }
+
+    /// Adds the current sparsity of each layer, speeds, and the state of the
+    /// selected cell to the plots. Called whenever a new frame is displayed.
+    fn record_plot_samples(&mut self) {
+        let cycle = self.cur_cycle();
+
+        if self.hex_grid.buffer.stats_enabled() {
+            for (tags, stats) in stats::layer_stats(&self.hex_grid.buffer).into_iter().rev() {
+                self.plot_panel.push("Active (%)", &tags, cycle,
+                    stats.active_fraction() * 100.0);
+            }
+        }
+
+        if self.replay.is_none() {
+            self.plot_panel.push("Cycles/s", "cps", cycle, self.cycle_status.cur_cps() as f32);
+        }
+        self.plot_panel.push("FPS", "fps", cycle, self.stats.fps());
+
+        let selected_state = self.selection.as_ref()
+            .and_then(|sel| self.hex_grid.buffer.axn_state(sel.pick().axn_idx));
+        match selected_state {
+            Some(state) => self.plot_panel.push("Selected cell", "state", cycle, state as f32),
+            None => self.plot_panel.remove("Selected cell", "state"),
+        }
+    }
+
+    /// Starts recording the raw tract stream to a new file or finishes the
+    /// current recording.
+    fn toggle_tract_recording(&mut self) {
+        if let Some(mut tract_writer) = self.tract_writer.take() {
+            match tract_writer.flush() {
+                Ok(()) => println!("VIBI: Recorded {} tract frames.", tract_writer.frame_count()),
+                Err(e) => println!("VIBI: Error finishing tract recording: {}", e),
+            }
+            return;
+        }
+
+        if self.replay.is_some() {
+            println!("VIBI: Tract streams cannot be recorded during a replay.");
+            return;
+        }
+
+        let path = match time::strftime("%Y%m%d-%H%M%S", &time::now()) {
+            Ok(timestamp) => self.output_dir.join(format!("vibi_tract_{}.vtr", timestamp)),
+            Err(e) => {
+                println!("VIBI: Error formatting timestamp: {}", e);
+                return;
+            },
+        };
+
+        match TractWriter::create(&path, &self.area_name, self.hex_grid.buffer.layout()) {
+            Ok(tract_writer) => {
+                println!("VIBI: Recording tract stream to '{}'...", path.display());
+                self.tract_writer = Some(tract_writer);
+            },
+            Err(e) => println!("VIBI: Error creating '{}': {}", path.display(), e),
+        }
+    }
+
+    /// Displays fresh states from the main sampler, if any, recording them
+    /// wherever enabled and counting them towards the current step.
+    fn refresh_main_grid(&mut self) {
+        if !self.hex_grid.buffer.refresh_vertex_buf() { return; }
+
+        self.throttle.consume();
+        self.write_tract_frame();
+        self.record_plot_samples();
+
+        if self.step_target.is_some() {
+            self.step_frame_received = true;
+            self.try_finish_step();
+        }
+    }
+
+    /// Writes the most recently received axon states to the tract stream.
+    fn write_tract_frame(&mut self) {
+        let cycle = self.cur_cycle();
+        let res = match self.tract_writer {
+            Some(ref mut tract_writer) => {
+                let raw_states = self.hex_grid.buffer.raw_states_vec();
+                let states = raw_states.lock().unwrap();
+                tract_writer.write_frame(cycle, &states)
+            },
+            None => return,
+        };
+
+        if let Err(e) = res {
+            println!("VIBI: Error writing tract frame: {}", e);
+            self.toggle_tract_recording();
+        }
+    }
+
+    /// Finishes any recordings in progress.
+    fn finish_recordings(&mut self) {
+        if self.recorder.is_some() { self.toggle_recording(); }
+        if self.tract_writer.is_some() { self.toggle_tract_recording(); }
+    }
+
+    /// Starts a new recording or finishes the current one.
+    fn toggle_recording(&mut self) {
+        match self.recorder.take() {
+            Some(recorder) => {
+                let (dir, frame_count) = (recorder.dir().to_owned(), recorder.frame_count());
+                match recorder.finish() {
+                    Ok(()) => println!("VIBI: Recorded {} frames to '{}'.", frame_count,
+                        dir.display()),
+                    Err(e) => println!("VIBI: Error finishing recording: {}", e),
+                }
+            },
+            None => {
+                let cycle = self.cur_cycle();
+                match Recorder::start(&self.output_dir, self.record_every, cycle,
+                        self.encoder_cmd.as_ref().map(|c| c.as_str()))
+                {
+                    Ok(recorder) => {
+                        println!("VIBI: Recording every {} cycles to '{}'...",
+                            self.record_every, recorder.dir().display());
+                        self.recorder = Some(recorder);
+                    },
+                    Err(e) => println!("VIBI: Error starting recording: {}", e),
+                }
+            },
+        }
+    }
+
+    /// Selects the cell under `pos`, highlighting its synapse sources.
+    ///
+    /// Clears the selection if `pos` is not over a cell.
+    fn select_cell(&mut self, pos: (i32, i32)) {
+        let pick = self.hex_grid.pick(pos);
+        let layer = pick.and_then(|pick| {
+            self.cell_layers.iter()
+                .find(|lyr| lyr.slc_range.start <= pick.slc_id && pick.slc_id < lyr.slc_range.end)
+                .cloned()
+        });
+
+        let (pick, layer) = match (pick, layer) {
+            (Some(pick), Some(layer)) => (pick, layer),
+            _ => {
+                self.selection = None;
+                self.hex_grid.buffer.clear_highlights();
+                return;
+            },
+        };
+
+        // Reuse the existing samplers when selecting within the same layer:
+        if let Some(ref mut selection) = self.selection {
+            if selection.layer().addr == layer.addr {
+                selection.reselect(pick, self.hex_grid.buffer.layout());
+                return;
+            }
+        }
+
+        for &buf in SynBuffer::all().iter() {
+            self.pending_samplers.push_back(SamplerDest::Selection(layer.addr, buf));
+            self.request_tx.send(Request::Sampler {
+                area_name: self.area_name.clone(),
+                kind: buf.sampler_kind(layer.addr),
+                buffer_kind: SamplerBufferKind::Single,
+                backpressure: false,
+            }).unwrap();
+        }
+        self.command_tx.send(Command::None).unwrap();
+
+        self.hex_grid.buffer.clear_highlights();
+        self.selection = Some(Selection::new(layer, pick, self.hex_grid.buffer.layout()));
+    }
+
     /// Moves the camera position in our out (horizontal scrolling ignored).
     #[allow(dead_code)]
     fn handle_mouse_wheel(&mut self, scroll_delta: MouseScrollDelta) {
@@ -466,17 +1736,29 @@ impl<'d> Window<'d> {
         };
         let _ = hrz;
 
-        self.hex_grid.zoom_camera(vrt);
+        for hex_grid in self.hex_grids_mut() {
+            hex_grid.zoom_camera(vrt);
+        }
     }
 
     fn handle_mouse_moved(&mut self, pos: (f64, f64)) {
         let pos = (pos.0 as i32, pos.1 as i32);
         self.mouse_pos = pos;
 
-        if let Some(ref mut start_pos) = self.dragging {
+        if let Some(start_pos) = self.dragging {
             let delta = (pos.0 - start_pos.0, pos.1 - start_pos.1);
-            self.hex_grid.move_camera(delta);
-            *start_pos = pos;
+            for hex_grid in self.hex_grids_mut() {
+                hex_grid.move_camera(delta);
+            }
+            self.dragging = Some(pos);
+        }
+
+        if let Some(start_pos) = self.orbiting {
+            let delta = (pos.0 - start_pos.0, pos.1 - start_pos.1);
+            for hex_grid in self.hex_grids_mut() {
+                hex_grid.orbit_camera(delta);
+            }
+            self.orbiting = Some(pos);
         }
     }
 
@@ -485,10 +1767,35 @@ impl<'d> Window<'d> {
         match button {
             MouseButton::Left => {
                 match button_state {
-                    ElementState::Pressed => self.dragging = Some(self.mouse_pos),
-                    ElementState::Released => self.dragging = None,
+                    ElementState::Pressed => {
+                        self.dragging = Some(self.mouse_pos);
+                        self.click_start = Some(self.mouse_pos);
+                    },
+                    ElementState::Released => {
+                        self.dragging = None;
+
+                        // A press and release without moving is a click:
+                        if let Some(start) = self.click_start.take() {
+                            let moved = (self.mouse_pos.0 - start.0).abs()
+                                + (self.mouse_pos.1 - start.1).abs();
+                            if moved <= CLICK_MAX_MOVE && self.has_mouse_focus {
+                                let pos = self.mouse_pos;
+                                if self.layer_list.click(pos, &mut self.hex_grid.buffer) {
+                                    self.hex_grid.fit_to_view();
+                                } else {
+                                    self.select_cell(pos);
+                                }
+                            }
+                        }
+                    },
                 }
             },
+            MouseButton::Right => {
+                self.orbiting = match button_state {
+                    ElementState::Pressed => Some(self.mouse_pos),
+                    ElementState::Released => None,
+                };
+            },
             _ => (),
         }
         // println!("WINDOW::HANDLE_MOUSE_INPUT(): focus: {}, dragging: {:?}", self.has_mouse_focus, self.dragging);
@@ -500,4 +1807,16 @@ impl<'d> SetMouseFocus for Window<'d> {
         self.has_mouse_focus = focus;
         // println!("WINDOW::SET_MOUSE_FOCUS(): Setting focus to: {}, dragging: {:?}", focus, self.dragging);
     }
-}
\ No newline at end of file
+}
+
+
+/// Formats an iteration count the way it is typed into the 'Iters:' box.
+fn format_iters(iters: u32) -> String {
+    if iters >= 1000000 && iters % 1000000 == 0 {
+        format!("{}m", iters / 1000000)
+    } else if iters >= 1000 && iters % 1000 == 0 {
+        format!("{}k", iters / 1000)
+    } else {
+        iters.to_string()
+    }
+}
@@ -0,0 +1,81 @@
+//! Smoke tests for the built-in presets (schemes only, no cortex is built).
+
+extern crate vibi;
+
+use vibi::presets;
+use vibi::bismit::map::{LayerMapScheme, LayerKind, AxonDomain};
+
+
+fn build_schemes(name: &str) {
+    let preset = presets::preset(name).expect("preset not registered");
+    let config = (preset.config)();
+    let areas = config.a_schemes.areas();
+
+    let has_area = |name: &str| areas.iter().any(|a| a.name() == name);
+    assert!(has_area(&config.view_area), "unknown view area: '{}'", config.view_area);
+    assert!(!config.input_areas.is_empty());
+    for input_area in config.input_areas.iter() {
+        assert!(has_area(input_area), "unknown input area: '{}'", input_area);
+    }
+
+    let lm_schemes: Vec<&LayerMapScheme> = areas.iter().map(|area| {
+        config.lm_schemes.maps().iter().find(|lm| lm.name() == area.layer_map_name())
+            .unwrap_or_else(|| panic!("area '{}': unknown layer map: '{}'", area.name(),
+                area.layer_map_name()))
+    }).collect();
+
+    // Every input layer read by a tuft must be fed by an output of some area:
+    let produced = |sig_tags| lm_schemes.iter().any(|lm| lm.layers().iter().any(|lyr| {
+        match *lyr.axn_domain() {
+            AxonDomain::Output(ref sig) => sig.tags() == sig_tags,
+            _ => false,
+        }
+    }));
+
+    for lm in lm_schemes.iter() {
+        for lyr in lm.layers().iter() {
+            let cell_scheme = match lyr.kind() {
+                Some(&LayerKind::Cellular(ref cell_scheme)) => cell_scheme,
+                _ => continue,
+            };
+            for src_name in cell_scheme.tft_schemes().iter()
+                    .flat_map(|tft| tft.src_lyrs().iter().map(|src| src.name()))
+            {
+                let src_lyr = lm.layers().iter().find(|l| l.name() == src_name)
+                    .unwrap_or_else(|| panic!("layer map '{}': layer '{}': unknown source \
+                        layer: '{}'", lm.name(), lyr.name(), src_name));
+
+                if let AxonDomain::Input(ref sigs) = *src_lyr.axn_domain() {
+                    for sig in sigs.iter() {
+                        assert!(produced(sig.tags()), "layer map '{}': input layer '{}' (read \
+                            by '{}') is fed by no area", lm.name(), src_name, lyr.name());
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn default() { build_schemes("default"); }
+
+#[test]
+fn glyph_seq() { build_schemes("glyph_seq"); }
+
+#[test]
+fn scalar_sdr() { build_schemes("scalar_sdr"); }
+
+#[test]
+fn skewed_hex() { build_schemes("skewed_hex"); }
+
+#[test]
+fn scalar_seq() { build_schemes("scalar_seq"); }
+
+#[test]
+fn multi() { build_schemes("multi"); }
+
+#[test]
+fn motor() { build_schemes("motor"); }
+
+#[test]
+fn multi_cere() { build_schemes("multi_cere"); }